@@ -0,0 +1,35 @@
+use crate::addon;
+use crate::config::Config;
+
+pub fn start(config: &mut Config, name: &str) {
+    let target = addon::find(config, name);
+    let dependents: Vec<String> = addon::all(config)
+        .into_iter()
+        .filter(|addon| addon.enabled && addon.depends_on.iter().any(|dep| dep == name))
+        .map(|addon| addon.name)
+        .collect();
+    if !dependents.is_empty() {
+        panic!(
+            "Add-on `{}` is needed by {}, disable them first",
+            name,
+            dependents.join(", ")
+        );
+    }
+
+    tracing::info!("Removing add-on {}...", name);
+    let path = addon::send(config, &target);
+    if !addon::on_master(
+        config,
+        &format!("kubectl delete --ignore-not-found -f {}", path),
+        false,
+    )
+    .success()
+    {
+        panic!("Failed to remove add-on `{}`", name);
+    }
+    if let Some(addon) = config.addons.iter_mut().find(|addon| addon.name == name) {
+        addon.enabled = false;
+        config.save();
+    }
+    tracing::info!("Add-on {} disabled", name);
+}
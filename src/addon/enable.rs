@@ -0,0 +1,25 @@
+use crate::addon;
+use crate::config::{Addon, Config};
+use std::collections::HashMap;
+
+pub fn start(config: &mut Config, name: &str) {
+    // Make sure the add-on is known before touching `config.yaml`.
+    addon::find(config, name);
+    match config.addons.iter_mut().find(|addon| addon.name == name) {
+        Some(addon) => addon.enabled = true,
+        None => config.addons.push(Addon {
+            name: name.to_owned(),
+            enabled: true,
+            path: None,
+            depends_on: Vec::new(),
+            vars: HashMap::new(),
+        }),
+    }
+
+    addon::wait_control_plane(config);
+    for addon in addon::ordered(config, &[name.to_owned()]) {
+        addon::apply(config, &addon);
+    }
+    config.save();
+    tracing::info!("Add-on {} enabled", name);
+}
@@ -0,0 +1,20 @@
+use crate::addon;
+use crate::config::Config;
+
+pub fn start(config: &Config) {
+    println!("{:<24}{:<10}{:<24}SOURCE", "NAME", "ENABLED", "DEPENDS ON");
+    for addon in addon::all(config) {
+        let source = match &addon.path {
+            Some(path) => path.to_owned(),
+            None => "built-in".to_owned(),
+        };
+        let depends_on = match addon.depends_on.is_empty() {
+            true => "-".to_owned(),
+            false => addon.depends_on.join(","),
+        };
+        println!(
+            "{:<24}{:<10}{:<24}{}",
+            addon.name, addon.enabled, depends_on, source
+        );
+    }
+}
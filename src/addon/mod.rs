@@ -0,0 +1,289 @@
+pub mod disable;
+pub mod enable;
+pub mod list;
+pub mod status;
+
+use crate::config::{Addon, Config};
+use crate::health::Component;
+use crate::networking;
+use crate::template;
+use crate::workdir;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Rendered manifests, kept locally and on the first master.
+const REMOTE_DIR: &str = "/opt/kubernetes/cfg/addons";
+
+pub struct Builtin {
+    pub name: &'static str,
+    manifest: &'static str,
+    depends_on: &'static [&'static str],
+    // Defaults of the placeholders the manifest has on top of cluster ones.
+    vars: &'static [(&'static str, &'static str)],
+}
+
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "metrics-server",
+        manifest: include_str!("../../templates/addons/metrics-server.yaml"),
+        depends_on: &[],
+        vars: &[],
+    },
+    Builtin {
+        name: "ingress-nginx",
+        manifest: include_str!("../../templates/addons/ingress-nginx.yaml"),
+        depends_on: &[],
+        vars: &[("http_node_port", "30080"), ("https_node_port", "30443")],
+    },
+    Builtin {
+        name: "local-path-storage",
+        manifest: include_str!("../../templates/addons/local-path-storage.yaml"),
+        depends_on: &[],
+        vars: &[
+            ("storage_path", "/opt/local-path-provisioner"),
+            ("default_class", "true"),
+        ],
+    },
+    Builtin {
+        name: "dashboard",
+        manifest: include_str!("../../templates/addons/dashboard.yaml"),
+        depends_on: &["metrics-server"],
+        vars: &[("node_port", "30843")],
+    },
+];
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// Add-on as configured, built-in ones getting their dependencies and
+/// variable defaults merged in.
+fn effective(addon: &Addon) -> Addon {
+    let mut addon = addon.clone();
+    if addon.path.is_none() {
+        if let Some(builtin) = builtin(&addon.name) {
+            for dependency in builtin.depends_on {
+                if !addon.depends_on.iter().any(|name| name == dependency) {
+                    addon.depends_on.push(dependency.to_string());
+                }
+            }
+            for (name, value) in builtin.vars {
+                addon
+                    .vars
+                    .entry(name.to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+        }
+    }
+    addon
+}
+
+/// Every add-on known, configured ones first, then built-in ones left out of
+/// `config.yaml` as disabled.
+pub fn all(config: &Config) -> Vec<Addon> {
+    let mut addons: Vec<Addon> = config.addons.iter().map(effective).collect();
+    for builtin in BUILTINS {
+        if !config.addons.iter().any(|addon| addon.name == builtin.name) {
+            addons.push(effective(&Addon {
+                name: builtin.name.to_owned(),
+                enabled: false,
+                path: None,
+                depends_on: Vec::new(),
+                vars: HashMap::new(),
+            }));
+        }
+    }
+    addons
+}
+
+pub fn find(config: &Config, name: &str) -> Addon {
+    all(config)
+        .into_iter()
+        .find(|addon| addon.name == name)
+        .unwrap_or_else(|| {
+            panic!(
+                "Add-on `{}` is neither built-in nor listed in `addons`, \
+                set its `path` in `config.yaml`",
+                name
+            )
+        })
+}
+
+/// Enabled add-ons needed by `names`, dependencies coming first.
+pub fn ordered(config: &Config, names: &[String]) -> Vec<Addon> {
+    fn visit(addons: &[Addon], name: &str, visiting: &mut Vec<String>, sorted: &mut Vec<Addon>) {
+        if sorted.iter().any(|addon| addon.name == name) {
+            return;
+        }
+        if visiting.iter().any(|visited| visited == name) {
+            panic!(
+                "Add-ons depend on each other: {} -> {}",
+                visiting.join(" -> "),
+                name
+            );
+        }
+        let addon = addons
+            .iter()
+            .find(|addon| addon.name == name)
+            .unwrap_or_else(|| panic!("Add-on `{}` is not known", name));
+        visiting.push(name.to_owned());
+        for dependency in &addon.depends_on {
+            let enabled = addons
+                .iter()
+                .any(|addon| &addon.name == dependency && addon.enabled);
+            if !enabled {
+                panic!(
+                    "Add-on `{}` depends on `{}`, which is not enabled",
+                    name, dependency
+                );
+            }
+            visit(addons, dependency, visiting, sorted);
+        }
+        visiting.pop();
+        sorted.push(addon.clone());
+    }
+
+    let addons = all(config);
+    let mut sorted = Vec::new();
+    for name in names {
+        visit(&addons, name, &mut Vec::new(), &mut sorted);
+    }
+    sorted
+}
+
+fn source(addon: &Addon) -> String {
+    let Some(path) = &addon.path else {
+        return builtin(&addon.name)
+            .unwrap_or_else(|| panic!("Add-on `{}` has no `path` set", addon.name))
+            .manifest
+            .to_owned();
+    };
+    // Relative paths are taken from the directory of the cluster.
+    let path = workdir::cluster().join(path);
+    if path.is_file() {
+        return fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("Error happened when trying to read {}", path.display()));
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(&path)
+        .unwrap_or_else(|_| panic!("Error happened when trying to read {}", path.display()))
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|file| {
+            matches!(
+                file.extension().and_then(|extension| extension.to_str()),
+                Some("yaml" | "yml")
+            )
+        })
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .map(|file| {
+            fs::read_to_string(file)
+                .unwrap_or_else(|_| panic!("Error happened when trying to read {}", file.display()))
+        })
+        .collect::<Vec<_>>()
+        .join("\n---\n")
+}
+
+/// Manifest of an add-on, placeholders filled from the cluster settings and
+/// the add-on `vars`.
+pub fn manifest(config: &Config, addon: &Addon) -> String {
+    let mut vars = vec![
+        ("pod_cidr", config.networking.pod_cidr.as_str()),
+        ("cluster_domain", config.networking.cluster_domain.as_str()),
+        ("dns_service_ip", config.networking.dns_service_ip.as_str()),
+    ];
+    for (name, value) in &addon.vars {
+        vars.retain(|(key, _)| key != name);
+        vars.push((name, value));
+    }
+    template::render(&source(addon), &vars)
+}
+
+/// Run a shell command on the first master, where manifests are applied from.
+pub fn on_master(config: &Config, command: &str, quiet: bool) -> ExitStatus {
+    let (ip, _) = config.first_master();
+    let mut ssh = Command::new("ssh");
+    ssh.arg(format!("root@{}", ip)).arg(command);
+    if quiet {
+        ssh.stdout(Stdio::null()).stderr(Stdio::null());
+    }
+    ssh.status()
+        .expect("Error happened when trying to execute command on master")
+}
+
+/// Run `command` on the first master quietly, `input` being its standard input.
+pub fn on_master_with_input(config: &Config, command: &str, input: &str) -> ExitStatus {
+    let (ip, _) = config.first_master();
+    let mut ssh = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Error happened when trying to execute command on master");
+    // A command exiting early closes its input, which is fine.
+    let _ = ssh.stdin.take().unwrap().write_all(input.as_bytes());
+    ssh.wait()
+        .expect("Error happened when trying to execute command on master")
+}
+
+/// Render an add-on and send it to the first master, returning its path there.
+pub fn send(config: &Config, addon: &Addon) -> String {
+    let local_dir = workdir::staging("addons");
+    fs::create_dir_all(&local_dir).expect("Error happened when trying to create path");
+    let file = format!("{}.yaml", addon.name);
+    let local = local_dir.join(&file);
+    fs::write(&local, manifest(config, addon))
+        .expect("Error happened when trying to write add-on manifest");
+
+    let (ip, _) = config.first_master();
+    on_master(config, &format!("mkdir -p {}", REMOTE_DIR), false);
+    Command::new("scp")
+        .arg(&local)
+        .arg(format!("root@{}:{}/", networking::host(ip), REMOTE_DIR))
+        .status()
+        .expect("Error happened when trying to send files to other nodes");
+    format!("{}/{}", REMOTE_DIR, file)
+}
+
+pub fn apply(config: &Config, addon: &Addon) {
+    tracing::info!("Applying add-on {}...", addon.name);
+    let path = send(config, addon);
+    if !on_master(config, &format!("kubectl apply -f {}", path), false).success() {
+        panic!("Failed to apply add-on `{}`", addon.name);
+    }
+    tracing::info!("Add-on {} applied", addon.name);
+}
+
+/// Wait for the apiserver to report itself ready before applying anything.
+pub fn wait_control_plane(config: &Config) {
+    let timeout = Component::Apiserver.timeout(config);
+    tracing::info!(
+        "Waiting up to {}s for the control plane to become healthy...",
+        timeout
+    );
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    loop {
+        if on_master(config, "kubectl get --raw /readyz", true).success() {
+            tracing::info!("Control plane is healthy");
+            return;
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(Duration::from_secs(5));
+    }
+    panic!(
+        "Control plane did not become healthy within {}s, please check \
+        `kubectl get --raw '/readyz?verbose'`",
+        timeout
+    );
+}
@@ -0,0 +1,20 @@
+use crate::addon;
+use crate::config::Config;
+
+pub fn start(config: &Config, name: Option<&str>) {
+    let addons = match name {
+        Some(name) => vec![addon::find(config, name)],
+        None => addon::all(config),
+    };
+    println!("{:<24}{:<10}STATE", "NAME", "ENABLED");
+    for addon in addons {
+        // Every object of the manifest has to exist for it to count as applied.
+        let manifest = addon::manifest(config, &addon);
+        let applied = addon::on_master_with_input(config, "kubectl get -f -", &manifest).success();
+        let state = match applied {
+            true => "applied",
+            false => "not applied",
+        };
+        println!("{:<24}{:<10}{}", addon.name, addon.enabled, state);
+    }
+}
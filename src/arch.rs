@@ -0,0 +1,102 @@
+use crate::config::Config;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::process::Command;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Arch {
+    Amd64,
+    Arm64,
+}
+
+impl Arch {
+    /// Parse either a Go style name (`amd64`) or the output of `uname -m`.
+    pub fn parse(name: &str) -> Option<Arch> {
+        match name.trim() {
+            "amd64" | "x86_64" => Some(Arch::Amd64),
+            "arm64" | "aarch64" => Some(Arch::Arm64),
+            _ => None,
+        }
+    }
+
+    /// Name used by Kubernetes, etcd and most release archives.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Arch::Amd64 => "amd64",
+            Arch::Arm64 => "arm64",
+        }
+    }
+
+    /// Name reported by `uname -m`, used by docker release archives.
+    pub fn machine(&self) -> &'static str {
+        match self {
+            Arch::Amd64 => "x86_64",
+            Arch::Arm64 => "aarch64",
+        }
+    }
+
+    /// Architecture of the machine running rk8s.
+    pub fn local() -> Arch {
+        let output = Command::new("uname")
+            .arg("-m")
+            .output()
+            .expect("Error happened when trying to execute `uname`");
+        let machine = String::from_utf8_lossy(&output.stdout);
+        Arch::parse(&machine)
+            .unwrap_or_else(|| panic!("Architecture `{}` is not supported", machine.trim()))
+    }
+
+    /// Architecture of a node, as set in `config.yaml` or detected over ssh.
+    pub fn of(config: &Config, ip: &String) -> Arch {
+        if let Some(name) = config.instance_archs.get(ip) {
+            return Arch::parse(name).unwrap_or_else(|| {
+                panic!("Architecture `{}` set for {} is not supported", name, ip)
+            });
+        }
+        let output = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("uname -m")
+            .output()
+            .expect("Error happened when trying to detect architecture of other nodes");
+        let machine = String::from_utf8_lossy(&output.stdout);
+        Arch::parse(&machine).unwrap_or_else(|| {
+            panic!(
+                "Failed to detect architecture of {}, got `{}`, please set it in `instance_archs`",
+                ip,
+                machine.trim()
+            )
+        })
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Detect the architecture of every node lacking one in `instance_archs`,
+/// so later phases do not need to probe them again.
+pub fn resolve(config: &mut Config) {
+    let mut archs = HashMap::new();
+    for (ip, name) in &config.instance_hosts {
+        let arch = Arch::of(config, ip);
+        tracing::info!("Found {} on {} running {}", name, ip, arch);
+        archs.insert(ip.to_owned(), arch.as_str().to_owned());
+    }
+    config.instance_archs = archs;
+}
+
+/// Architecture of every node in `instance_hosts`.
+pub fn of_hosts(config: &Config) -> HashMap<String, Arch> {
+    config
+        .instance_hosts
+        .keys()
+        .map(|ip| (ip.to_owned(), Arch::of(config, ip)))
+        .collect()
+}
+
+/// Distinct architectures among the given nodes.
+pub fn distinct(archs: &HashMap<String, Arch>) -> BTreeSet<Arch> {
+    archs.values().copied().collect()
+}
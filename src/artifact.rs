@@ -0,0 +1,457 @@
+use crate::arch::Arch;
+use crate::config::Config;
+use crate::version::KubeVersion;
+use crate::workdir;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Pinned SHA-256 of known releases, as `(component, version, arch, digest)`,
+// copied from the checksums published with a release when it is added here.
+// Nothing is fetched at deploy time, a mirror serving another file is caught
+// by the mismatch. Releases missing here need their digest in `config.yaml`.
+const PINNED_DIGESTS: &[(&str, &str, &str, &str)] = &[];
+
+/// Digests of the artifacts imported from an offline bundle, keyed by URL.
+pub fn local_digests() -> PathBuf {
+    workdir::preparation().join("SHA256SUMS")
+}
+
+/// A release archive rk8s deploys, derived from the versions in `config.yaml`.
+pub struct Artifact {
+    // Name of the component, prefixing its `_sha256` field in `config.yaml`.
+    pub component: &'static str,
+    pub version: String,
+    pub url: String,
+    pub arch: Arch,
+    // Archive name, both downloaded and staged under `preparation/<arch>` as such.
+    pub file: String,
+    // Directory the archive unpacks its binaries into.
+    pub dir: String,
+    sha256: Option<String>,
+}
+
+impl Artifact {
+    fn from(
+        component: &'static str,
+        url: &str,
+        version: &str,
+        sha256: &HashMap<String, String>,
+        arch: Arch,
+        dir: &str,
+    ) -> Artifact {
+        let url = url
+            .replace("{version}", version)
+            .replace("{arch}", arch.as_str())
+            .replace("{machine}", arch.machine());
+        Artifact {
+            component,
+            version: version.to_owned(),
+            file: file_name(&url).to_owned(),
+            url,
+            arch,
+            dir: dir.to_owned(),
+            sha256: sha256.get(arch.as_str()).cloned(),
+        }
+    }
+
+    pub fn etcd(config: &Config, arch: Arch) -> Artifact {
+        let mut etcd = Artifact::from(
+            "etcd",
+            &config.etcd_url,
+            &config.etcd_version,
+            &config.etcd_sha256,
+            arch,
+            "",
+        );
+        // Etcd unpacks into a folder named after the archive.
+        etcd.dir = etcd.file.split(".tar.").next().unwrap().to_owned();
+        etcd
+    }
+
+    pub fn docker(config: &Config, arch: Arch) -> Artifact {
+        Artifact::from(
+            "docker",
+            &config.docker_url,
+            &config.docker_version,
+            &config.docker_sha256,
+            arch,
+            "docker",
+        )
+    }
+
+    pub fn containerd(config: &Config, arch: Arch) -> Artifact {
+        Artifact::from(
+            "containerd",
+            &config.containerd_url,
+            &config.containerd_version,
+            &config.containerd_sha256,
+            arch,
+            "bin",
+        )
+    }
+
+    pub fn runc(config: &Config, arch: Arch) -> Artifact {
+        // Runc is released as a bare binary rather than an archive.
+        Artifact::from(
+            "runc",
+            &config.runc_url,
+            &config.runc_version,
+            &config.runc_sha256,
+            arch,
+            "",
+        )
+    }
+
+    pub fn youki(config: &Config, arch: Arch) -> Artifact {
+        Artifact::from(
+            "youki",
+            &config.youki_url,
+            &config.youki_version,
+            &config.youki_sha256,
+            arch,
+            "youki",
+        )
+    }
+
+    pub fn crun(config: &Config, arch: Arch) -> Artifact {
+        // Crun is released as a bare binary rather than an archive.
+        Artifact::from(
+            "crun",
+            &config.crun_url,
+            &config.crun_version,
+            &config.crun_sha256,
+            arch,
+            "",
+        )
+    }
+
+    pub fn crio(config: &Config, arch: Arch) -> Artifact {
+        let version = config.crio_version.to_owned().unwrap_or_else(|| {
+            // The first patch release is the one sure to exist for every minor.
+            format!("v1.{}.0", KubeVersion::from(config).minor)
+        });
+        Artifact::from(
+            "crio",
+            &config.crio_url,
+            &version,
+            &config.crio_sha256,
+            arch,
+            "cri-o",
+        )
+    }
+
+    pub fn kubernetes(config: &Config, arch: Arch) -> Artifact {
+        Artifact::from(
+            "kubernetes",
+            &config.kubernetes_url,
+            &config.kubernetes_version,
+            &config.kubernetes_sha256,
+            arch,
+            "kubernetes/server/bin",
+        )
+    }
+
+    pub fn cni(config: &Config, arch: Arch) -> Artifact {
+        // CNI plugins are packed without a top level folder.
+        Artifact::from(
+            "cni",
+            &config.cni_url,
+            &config.cni_version,
+            &config.cni_sha256,
+            arch,
+            "cni",
+        )
+    }
+
+    /// Where the archive is staged for deploying.
+    pub fn prepared(&self) -> PathBuf {
+        workdir::preparation()
+            .join(self.arch.as_str())
+            .join(&self.file)
+    }
+
+    pub fn expected_sha256(&self) -> String {
+        expected_sha256(
+            self.component,
+            &self.version,
+            self.arch,
+            &self.url,
+            &self.sha256,
+        )
+    }
+
+    /// Make sure a verified copy of the archive is staged under `preparation/`,
+    /// downloading it when it has not been prepared beforehand.
+    pub fn stage(&self) -> PathBuf {
+        let prepared = self.prepared();
+        let expected = self.expected_sha256();
+        if prepared.is_file() {
+            verify(&prepared, &expected);
+        } else {
+            tracing::info!(
+                "{} not prepared, downloading {}",
+                prepared.display(),
+                self.url
+            );
+            fs::create_dir_all(prepared.parent().unwrap())
+                .expect("Error happened when trying to create `preparation` directory");
+            download(&self.url, &prepared, &expected);
+        }
+        prepared
+    }
+}
+
+/// Retrieve the file name from an artifact URL.
+pub fn file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// Resolve the SHA-256 the release `version` of `component` for `arch`,
+/// downloaded from `url`, is expected to have.
+///
+/// A digest set in `config.yaml` always wins, then the pinned one, then the
+/// one recorded by `rk8s bundle import`. Anything else is refused.
+pub fn expected_sha256(
+    component: &str,
+    version: &str,
+    arch: Arch,
+    url: &str,
+    configured: &Option<String>,
+) -> String {
+    if let Some(digest) = trusted_sha256(component, version, arch, configured) {
+        return digest;
+    }
+
+    // Bundles only vouch for what neither `config.yaml` nor rk8s knows.
+    fs::read_to_string(local_digests())
+        .ok()
+        .and_then(|content| find_digest(&content, url))
+        .unwrap_or_else(|| {
+            panic!(
+                "No known SHA-256 for {} {} ({}), please set `{}_sha256` in `config.yaml`",
+                component,
+                version,
+                arch.as_str(),
+                component
+            )
+        })
+}
+
+/// Digest known without any bundle, set in `config.yaml` or pinned.
+fn trusted_sha256(
+    component: &str,
+    version: &str,
+    arch: Arch,
+    configured: &Option<String>,
+) -> Option<String> {
+    match configured {
+        Some(digest) => Some(digest.trim().to_lowercase()),
+        None => pinned(component, version, arch),
+    }
+}
+
+/// Digests known without any bundle of every artifact `config.yaml` may
+/// download, keyed by URL.
+pub fn trusted_digests(config: &Config) -> HashMap<String, String> {
+    let mut digests = HashMap::new();
+    for arch in [Arch::Amd64, Arch::Arm64] {
+        let releases = [
+            Artifact::etcd(config, arch),
+            Artifact::docker(config, arch),
+            Artifact::containerd(config, arch),
+            Artifact::runc(config, arch),
+            Artifact::youki(config, arch),
+            Artifact::crun(config, arch),
+            Artifact::kubernetes(config, arch),
+            Artifact::cni(config, arch),
+        ];
+        for release in releases {
+            if let Some(digest) =
+                trusted_sha256(release.component, &release.version, arch, &release.sha256)
+            {
+                digests.insert(release.url, digest);
+            }
+        }
+    }
+    for (component, _, url, configured) in cfssl_sources(config) {
+        if let Some(digest) = trusted_sha256(component, cfssl_release(url), Arch::Amd64, configured)
+        {
+            digests.insert(url.to_owned(), digest);
+        }
+    }
+    digests
+}
+
+fn pinned(component: &str, version: &str, arch: Arch) -> Option<String> {
+    PINNED_DIGESTS
+        .iter()
+        .find(|(c, v, a, _)| *c == component && *v == version && *a == arch.as_str())
+        .map(|(_, _, _, digest)| digest.to_string())
+}
+
+/// Release of a cfssl binary, the directory it is published under.
+fn cfssl_release(url: &str) -> &str {
+    url.rsplit('/').nth(1).unwrap_or_default()
+}
+
+/// cfssl binaries, which sign every certificate of the cluster, as
+/// `(file name, url, expected SHA-256)`. They are staged right under
+/// `preparation/` as the file name.
+pub fn cfssl_tools(config: &Config) -> Vec<(&'static str, String, String)> {
+    cfssl_sources(config)
+        .into_iter()
+        .map(|(component, file, url, sha256)| {
+            let expected = expected_sha256(component, cfssl_release(url), Arch::Amd64, url, sha256);
+            (file, url.to_owned(), expected)
+        })
+        .collect()
+}
+
+// Component, file name, URL and configured digest of each cfssl binary.
+fn cfssl_sources(config: &Config) -> [(&'static str, &'static str, &String, &Option<String>); 3] {
+    [
+        ("cfssl", "cfssl", &config.cfssl_url, &config.cfssl_sha256),
+        (
+            "cfssljson",
+            "cfssljson",
+            &config.cfssljson_url,
+            &config.cfssljson_sha256,
+        ),
+        (
+            "cfsslcertinfo",
+            "cfssl-certinfo",
+            &config.cfsslcertinfo_url,
+            &config.cfsslcertinfo_sha256,
+        ),
+    ]
+}
+
+// Look `name` up in either a bare digest or `<digest>  <file name>` lines.
+fn find_digest(content: &str, name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let digest = fields.next()?;
+        match fields.next() {
+            Some(file) if file.trim_start_matches('*') != name => None,
+            _ => Some(digest.to_lowercase()),
+        }
+    })
+}
+
+/// Compute the SHA-256 of a file.
+pub fn sha256(path: &Path) -> String {
+    let output = Command::new("sha256sum")
+        .arg(path)
+        .output()
+        .expect("Error happened when trying to execute `sha256sum`");
+    if !output.status.success() {
+        panic!("Failed to compute SHA-256 of {}", path.display());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn check(path: &Path, expected: &str) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    let actual = sha256(path);
+    if actual != expected {
+        return Err(format!(
+            "SHA-256 mismatch for {}: expected {}, got {}. The file is corrupted, \
+            incomplete or not the release configured in `config.yaml`",
+            path.display(),
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+/// Check a file against its expected SHA-256, aborting on mismatch.
+pub fn verify(path: &Path, expected: &str) {
+    tracing::info!("Verifying SHA-256 of {}", path.display());
+    if let Err(e) = check(path, expected) {
+        panic!("{}", e);
+    }
+    tracing::info!("{} verified", path.display());
+}
+
+/// Download `url` to `dest` and verify it before handing it out.
+///
+/// The artifact is written to a `.part` file first, so an interrupted or
+/// tampered download never ends up under its final name.
+pub fn download(url: &str, dest: &Path, expected: &str) {
+    let partial = PathBuf::from(format!("{}.part", dest.display()));
+    let status = Command::new("curl")
+        .arg("-fL")
+        .arg(url)
+        .arg("-o")
+        .arg(&partial)
+        .status()
+        .expect("Error happened when trying to execute `curl`");
+    if !status.success() {
+        fs::remove_file(&partial).ok();
+        panic!(
+            "Failed to download {}, the transfer was incomplete, please try again",
+            url
+        );
+    }
+
+    tracing::info!("Verifying SHA-256 of {}", url);
+    if let Err(e) = check(&partial, expected) {
+        fs::remove_file(&partial).ok();
+        panic!("{}", e);
+    }
+    fs::rename(&partial, dest).expect("Error happened when trying to move downloaded artifact");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    #[ignore = "PINNED_DIGESTS has no entries checked against upstream checksums yet"]
+    fn template_releases_are_pinned() {
+        let config = config::template();
+        let mut missing = Vec::new();
+        for arch in [Arch::Amd64, Arch::Arm64] {
+            let releases = [
+                Artifact::etcd(&config, arch),
+                Artifact::docker(&config, arch),
+                Artifact::containerd(&config, arch),
+                Artifact::runc(&config, arch),
+                Artifact::youki(&config, arch),
+                Artifact::crun(&config, arch),
+                Artifact::kubernetes(&config, arch),
+                Artifact::cni(&config, arch),
+            ];
+            for release in releases {
+                if pinned(release.component, &release.version, arch).is_none() {
+                    missing.push(format!(
+                        "{} {} {}",
+                        release.component,
+                        release.version,
+                        arch.as_str()
+                    ));
+                }
+            }
+        }
+        for (component, url) in [
+            ("cfssl", &config.cfssl_url),
+            ("cfssljson", &config.cfssljson_url),
+            ("cfsslcertinfo", &config.cfsslcertinfo_url),
+        ] {
+            if pinned(component, cfssl_release(url), Arch::Amd64).is_none() {
+                missing.push(format!("{} {} amd64", component, cfssl_release(url)));
+            }
+        }
+        assert!(missing.is_empty(), "no pinned SHA-256 for {:?}", missing);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::addon;
+use crate::arch;
+use crate::artifact::{self, Artifact};
+use crate::bundle::{Entry, Manifest, FORMAT, MANIFEST};
+use crate::cni::Cni;
+use crate::config::Config;
+use crate::dns;
+use crate::oci::{OciRuntime, SMOKE_TEST_IMAGE};
+use crate::runtime::Runtime;
+use crate::workdir;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub fn start(config: &Config, output: Option<PathBuf>) {
+    // The CRI-O static bundle ships nothing able to import saved images, so
+    // its nodes would still pull them.
+    if let Runtime::CriO = Runtime::from(config) {
+        panic!(
+            "Offline bundles do not support `container_runtime: crio`, \
+            please use `containerd` or `docker` to deploy without network access"
+        );
+    }
+    tracing::info!("Start creating offline bundle...");
+    let staging = &workdir::home().join("bundle/staging");
+    if staging.is_dir() {
+        fs::remove_dir_all(staging).expect("Error happened when trying to clean bundle staging");
+    }
+    fs::create_dir_all(staging).expect("Error happened when trying to create bundle staging");
+
+    let mut artifacts = Vec::new();
+    let host_archs = arch::of_hosts(config);
+    let archs = arch::distinct(&host_archs);
+
+    // Binaries downloaded from the URLs in `config.yaml`, stored under the
+    // names the install and deploy phases look for.
+    let mut downloads: Vec<(String, String, String)> = artifact::cfssl_tools(config)
+        .into_iter()
+        .map(|(file, url, sha256)| (url, sha256, file.to_owned()))
+        .collect();
+    // Release archives are staged per architecture, as under `preparation/`.
+    for &arch in &archs {
+        fs::create_dir_all(staging.join(arch.as_str()))
+            .expect("Error happened when trying to create bundle staging");
+        let mut releases = vec![Artifact::etcd(config, arch)];
+        match Runtime::from(config) {
+            Runtime::Docker => releases.push(Artifact::docker(config, arch)),
+            Runtime::Containerd => {
+                releases.push(Artifact::containerd(config, arch));
+                releases.push(Artifact::runc(config, arch));
+            }
+            Runtime::CriO => unreachable!("CRI-O configurations are refused above"),
+        }
+        releases.push(Artifact::kubernetes(config, arch));
+        releases.push(Artifact::cni(config, arch));
+        // OCI runtimes picked by any node of this architecture.
+        let runtimes: Vec<OciRuntime> = config
+            .instance_hosts
+            .keys()
+            .filter(|ip| host_archs[*ip] == arch)
+            .map(|ip| OciRuntime::of(config, ip))
+            .collect();
+        for runtime in runtimes {
+            if let Some(release) = runtime.artifact(config, arch) {
+                if !releases.iter().any(|staged| staged.url == release.url) {
+                    releases.push(release);
+                }
+            }
+        }
+        for release in releases {
+            downloads.push((
+                release.url.to_owned(),
+                release.expected_sha256(),
+                format!("{}/{}", arch, release.file),
+            ));
+        }
+    }
+    for (url, sha256, file) in downloads {
+        tracing::info!("Downloading {}...", url);
+        artifact::download(&url, &staging.join(&file), &sha256);
+        artifacts.push(Entry {
+            file,
+            sha256,
+            source: url,
+        });
+    }
+
+    // Container images needed before any registry is reachable.
+    let mut images = vec![config.sandbox_image.to_owned(), SMOKE_TEST_IMAGE.to_owned()];
+    let mut manifests = vec![Cni::from(config).manifest(config), dns::manifest(config)];
+    let enabled: Vec<String> = config
+        .addons
+        .iter()
+        .filter(|addon| addon.enabled)
+        .map(|addon| addon.name.to_owned())
+        .collect();
+    for addon in addon::ordered(config, &enabled) {
+        manifests.push(addon::manifest(config, &addon));
+    }
+    for line in manifests.iter().flat_map(|manifest| manifest.lines()) {
+        if let Some(image) = line.trim().strip_prefix("image:") {
+            let image = image.trim().trim_matches('"').to_owned();
+            if !images.contains(&image) {
+                images.push(image);
+            }
+        }
+    }
+    for (arch, image) in archs
+        .iter()
+        .flat_map(|arch| images.iter().map(move |image| (arch, image)))
+    {
+        tracing::info!("Saving {} image {}...", arch, image);
+        fs::create_dir_all(staging.join("images").join(arch.as_str()))
+            .expect("Error happened when trying to create bundle staging");
+        let file = format!(
+            "images/{}/{}.tar",
+            arch,
+            image.replace(['/', ':', '@'], "_")
+        );
+        let pulled = Command::new("docker")
+            .arg("pull")
+            .arg(format!("--platform=linux/{}", arch))
+            .arg(image)
+            .status()
+            .expect("Error happened when trying to execute `docker`, is docker installed?");
+        let saved = Command::new("docker")
+            .arg("save")
+            .arg("-o")
+            .arg(staging.join(&file))
+            .arg(image)
+            .status()
+            .expect("Error happened when trying to execute `docker`, is docker installed?");
+        if !pulled.success() || !saved.success() {
+            panic!("Failed to save image {} into the bundle", image);
+        }
+        artifacts.push(Entry {
+            sha256: artifact::sha256(&staging.join(&file)),
+            file,
+            source: image.to_owned(),
+        });
+    }
+
+    let manifest = Manifest {
+        format: FORMAT,
+        rk8s_version: env!("CARGO_PKG_VERSION").to_owned(),
+        kubernetes_version: config.kubernetes_version.to_owned(),
+        artifacts,
+    };
+    let content = serde_yaml::to_string(&manifest)
+        .expect("Error happened when trying to serialize bundle manifest");
+    let mut manifest_file = File::create(staging.join(MANIFEST))
+        .expect("Error happened when trying to create bundle manifest");
+    manifest_file
+        .write_all(content.as_bytes())
+        .expect("Error happened when trying to write bundle manifest");
+    tracing::info!("Bundle manifest generated");
+
+    let output = output.unwrap_or_else(|| {
+        workdir::home().join(format!(
+            "rk8s-bundle-{}.tar.gz",
+            manifest.kubernetes_version
+        ))
+    });
+    tracing::info!("Packing bundle into {}...", output.display());
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&output)
+        .arg("-C")
+        .arg(staging)
+        .arg(".")
+        .status()
+        .expect("Error happened when trying to pack the bundle");
+    if !status.success() {
+        panic!("Failed to pack the bundle into {}", output.display());
+    }
+
+    let mut checksum = File::create(format!("{}.sha256", output.display()))
+        .expect("Error happened when trying to create bundle checksum");
+    writeln!(
+        &mut checksum,
+        "{}  {}",
+        artifact::sha256(&output),
+        artifact::file_name(&output.display().to_string())
+    )
+    .expect("Error happened when trying to write bundle checksum");
+
+    fs::remove_dir_all(staging).expect("Error happened when trying to clean bundle staging");
+    tracing::info!("Bundle {} created", output.display());
+}
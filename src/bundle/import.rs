@@ -0,0 +1,100 @@
+use crate::artifact;
+use crate::bundle::{Manifest, FORMAT, MANIFEST};
+use crate::config::Config;
+use crate::workdir;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn start(config: &Config, bundle: &Path) {
+    tracing::info!("Start importing offline bundle {}...", bundle.display());
+    if !bundle.is_file() {
+        panic!("{} does not exist", bundle.display());
+    }
+
+    let checksum = PathBuf::from(format!("{}.sha256", bundle.display()));
+    let content = fs::read_to_string(&checksum).unwrap_or_else(|_| {
+        panic!(
+            "{} not found, please copy it along with the bundle",
+            checksum.display()
+        )
+    });
+    let expected = content.split_whitespace().next().unwrap_or_default();
+    if expected.is_empty() {
+        panic!("{} holds no SHA-256", checksum.display());
+    }
+    artifact::verify(bundle, expected);
+
+    let staging = &workdir::home().join("bundle/import");
+    if staging.is_dir() {
+        fs::remove_dir_all(staging).expect("Error happened when trying to clean bundle staging");
+    }
+    fs::create_dir_all(staging).expect("Error happened when trying to create bundle staging");
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(bundle)
+        .arg("-C")
+        .arg(staging)
+        .status()
+        .expect("Error happened when trying to unpack the bundle");
+    if !status.success() {
+        panic!(
+            "Failed to unpack {}, the bundle is corrupted",
+            bundle.display()
+        );
+    }
+
+    let content = fs::read(staging.join(MANIFEST))
+        .expect("Bundle manifest not found, is this an rk8s bundle?");
+    let manifest: Manifest = serde_yaml::from_slice(&content)
+        .expect("Something went wrong while parsing bundle manifest");
+    if manifest.format != FORMAT {
+        panic!(
+            "Bundle format {} is not supported by this rk8s, expected {}",
+            manifest.format, FORMAT
+        );
+    }
+    tracing::info!(
+        "Bundle for kubernetes {} created by rk8s {}",
+        manifest.kubernetes_version,
+        manifest.rk8s_version
+    );
+
+    // Check everything before touching `preparation/`. The bundle may not
+    // contradict digests known without it.
+    let trusted = artifact::trusted_digests(config);
+    for entry in &manifest.artifacts {
+        if entry.sha256.trim().is_empty() {
+            panic!("{} has no SHA-256 in the bundle manifest", entry.file);
+        }
+        if let Some(digest) = trusted.get(&entry.source) {
+            if *digest != entry.sha256.trim().to_lowercase() {
+                panic!(
+                    "{} in the bundle has SHA-256 {}, but {} is expected for {}",
+                    entry.file, entry.sha256, digest, entry.source
+                );
+            }
+        }
+        artifact::verify(&staging.join(&entry.file), &entry.sha256);
+    }
+
+    let preparation = workdir::preparation();
+    let mut digests = File::create(artifact::local_digests())
+        .expect("Error happened when trying to create `SHA256SUMS`");
+    for entry in &manifest.artifacts {
+        let dest = preparation.join(&entry.file);
+        fs::create_dir_all(dest.parent().unwrap())
+            .expect("Error happened when trying to create directory under `preparation`");
+        fs::copy(staging.join(&entry.file), &dest)
+            .expect("Error happened when trying to copy artifact into `preparation`");
+        // Recorded under the URL the artifact is downloaded from, so phases
+        // resolving digests from `config.yaml` find it offline.
+        writeln!(&mut digests, "{}  {}", entry.sha256, entry.source)
+            .expect("Error happened when trying to write `SHA256SUMS`");
+        tracing::info!("{} imported", entry.file);
+    }
+
+    fs::remove_dir_all(staging).expect("Error happened when trying to clean bundle staging");
+    tracing::info!("Bundle imported into {}", preparation.display());
+}
@@ -0,0 +1,25 @@
+pub mod create;
+pub mod import;
+
+use serde::{Deserialize, Serialize};
+
+// Bumped whenever the layout of a bundle changes.
+pub const FORMAT: u32 = 1;
+pub const MANIFEST: &str = "manifest.yaml";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub format: u32,
+    pub rk8s_version: String,
+    pub kubernetes_version: String,
+    pub artifacts: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Entry {
+    // Path relative to the bundle root, mirrored under `preparation/`.
+    pub file: String,
+    pub sha256: String,
+    // URL, image reference or local path the artifact was gathered from.
+    pub source: String,
+}
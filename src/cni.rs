@@ -0,0 +1,114 @@
+use crate::config::Config;
+use crate::networking;
+use crate::template;
+use std::fmt;
+
+/// Pod network add-on, shipped as manifests embedded in the binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cni {
+    Calico,
+    Flannel,
+    Cilium,
+}
+
+impl Cni {
+    pub fn parse(name: &str) -> Option<Cni> {
+        match name.trim() {
+            "calico" => Some(Cni::Calico),
+            "flannel" => Some(Cni::Flannel),
+            "cilium" => Some(Cni::Cilium),
+            _ => None,
+        }
+    }
+
+    pub fn from(config: &Config) -> Cni {
+        let name = &config.network_plugin.name;
+        Cni::parse(name).unwrap_or_else(|| {
+            panic!(
+                "Network plugin `{}` is not supported, expected `calico`, `flannel` or `cilium`",
+                name
+            )
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cni::Calico => "calico",
+            Cni::Flannel => "flannel",
+            Cni::Cilium => "cilium",
+        }
+    }
+
+    fn template(&self) -> &'static str {
+        match self {
+            Cni::Calico => include_str!("../templates/cni/calico.yaml"),
+            Cni::Flannel => include_str!("../templates/cni/flannel.yaml"),
+            Cni::Cilium => include_str!("../templates/cni/cilium.yaml"),
+        }
+    }
+
+    pub fn manifest(&self, config: &Config) -> String {
+        let pod_cidr = &config.networking.pod_cidr;
+        let ipv4 = networking::cidr_of_family(pod_cidr, false);
+        let ipv6 = networking::cidr_of_family(pod_cidr, true);
+        let autodetect = |cidr: &Option<String>| match cidr {
+            Some(_) => "autodetect",
+            None => "none",
+        };
+
+        // Flannel refuses empty networks, only families in use are set.
+        let mut net_conf = serde_json::json!({
+            "EnableIPv4": ipv4.is_some(),
+            "EnableIPv6": ipv6.is_some(),
+            "Backend": { "Type": "vxlan" },
+        });
+        if let Some(cidr) = &ipv4 {
+            net_conf["Network"] = cidr.as_str().into();
+        }
+        if let Some(cidr) = &ipv6 {
+            net_conf["IPv6Network"] = cidr.as_str().into();
+        }
+        let net_conf = serde_json::to_string_pretty(&net_conf)
+            .unwrap()
+            .replace('\n', "\n    ");
+
+        template::render(
+            self.template(),
+            &[
+                ("pod_cidr", pod_cidr),
+                ("pod_cidr_ipv4", ipv4.as_deref().unwrap_or_default()),
+                ("pod_cidr_ipv6", ipv6.as_deref().unwrap_or_default()),
+                ("ipv4", &ipv4.is_some().to_string()),
+                ("ipv6", &ipv6.is_some().to_string()),
+                ("calico_ip", autodetect(&ipv4)),
+                ("calico_ip6", autodetect(&ipv6)),
+                ("net_conf", &net_conf),
+            ],
+        )
+    }
+
+    /// Namespace and name of the DaemonSet running the plugin on every node,
+    /// the network being usable once it is rolled out.
+    pub fn daemonset(&self) -> (&'static str, &'static str) {
+        match self {
+            Cni::Calico => ("kube-system", "calico-node"),
+            Cni::Flannel => ("kube-flannel", "kube-flannel-ds"),
+            Cni::Cilium => ("kube-system", "cilium"),
+        }
+    }
+
+    /// Shell command waiting for the plugin pods to become ready.
+    pub fn wait_command(&self) -> String {
+        let (namespace, name) = self.daemonset();
+        format!(
+            "kubectl -n {} rollout status daemonset/{} --timeout=300s",
+            namespace, name
+        )
+    }
+}
+
+impl fmt::Display for Cni {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
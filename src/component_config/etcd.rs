@@ -0,0 +1,100 @@
+use crate::config::{Config, ExtraArgs};
+use crate::networking;
+use serde::Serialize;
+use std::fs;
+
+/// `etcd.conf`, read by the unit as `ETCD_*` environment variables. etcd
+/// refuses a variable whose flag is also given, so the unit passes none.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct EtcdConfig {
+    name: String,
+    data_dir: String,
+    listen_peer_urls: String,
+    listen_client_urls: String,
+    initial_advertise_peer_urls: String,
+    advertise_client_urls: String,
+    initial_cluster: String,
+    initial_cluster_token: String,
+    initial_cluster_state: String,
+    cert_file: String,
+    key_file: String,
+    peer_cert_file: String,
+    peer_key_file: String,
+    trusted_ca_file: String,
+    peer_trusted_ca_file: String,
+    logger: String,
+    #[serde(skip)]
+    extra_args: ExtraArgs,
+}
+
+impl EtcdConfig {
+    /// Configuration of the member on the node `name` at `ip`.
+    pub fn from(config: &Config, ip: &str, name: &str) -> EtcdConfig {
+        let host = networking::host(ip);
+        let initial_cluster: Vec<String> = config
+            .instance_hosts
+            .iter()
+            .map(|(ip, name)| format!("etcd_{}=https://{}:2380", name, networking::host(ip)))
+            .collect();
+        EtcdConfig {
+            name: format!("etcd_{}", name),
+            data_dir: "/var/lib/etcd/default.etcd".to_owned(),
+            listen_peer_urls: format!("https://{}:2380", host),
+            listen_client_urls: format!("https://{}:2379", host),
+            initial_advertise_peer_urls: format!("https://{}:2380", host),
+            advertise_client_urls: format!("https://{}:2379", host),
+            initial_cluster: initial_cluster.join(","),
+            initial_cluster_token: "etcd-cluster".to_owned(),
+            initial_cluster_state: "new".to_owned(),
+            cert_file: "/opt/etcd/ssl/server.pem".to_owned(),
+            key_file: "/opt/etcd/ssl/server-key.pem".to_owned(),
+            peer_cert_file: "/opt/etcd/ssl/server.pem".to_owned(),
+            peer_key_file: "/opt/etcd/ssl/server-key.pem".to_owned(),
+            trusted_ca_file: "/opt/etcd/ssl/ca.pem".to_owned(),
+            peer_trusted_ca_file: "/opt/etcd/ssl/ca.pem".to_owned(),
+            logger: "zap".to_owned(),
+            extra_args: config.components.etcd.extra_args.clone(),
+        }
+    }
+
+    /// Content of the environment file, `extra_args` replacing the
+    /// variables of their flags.
+    pub fn to_env(&self) -> String {
+        // Mappings keep the order fields are declared in.
+        let fields = serde_yaml::to_value(self)
+            .expect("Error happened when trying to serialize etcd configuration");
+        let mut variables: Vec<(String, String)> = fields
+            .as_mapping()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.as_str().unwrap().to_owned(),
+                    value.as_str().unwrap_or_default().to_owned(),
+                )
+            })
+            .collect();
+        for (flag, value) in &self.extra_args.0 {
+            let key = flag.replace('-', "_").to_uppercase();
+            match variables.iter_mut().find(|(variable, _)| *variable == key) {
+                Some((_, old)) => *old = value.to_owned(),
+                None => variables.push((key, value.to_owned())),
+            }
+        }
+        let mut env = String::new();
+        for (key, value) in variables {
+            env.push_str(&format!(
+                "ETCD_{}=\"{}\"\n",
+                key,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            ));
+        }
+        env
+    }
+
+    pub fn write(&self, path: &str) {
+        fs::write(path, self.to_env())
+            .unwrap_or_else(|_| panic!("Error happened when trying to write `{}`", path));
+    }
+}
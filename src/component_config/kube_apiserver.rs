@@ -0,0 +1,66 @@
+use crate::component_config::Flags;
+use crate::config::Config;
+use crate::networking;
+use crate::version::KubeVersion;
+
+/// Flags of the apiserver listening on `ip`.
+pub fn flags(config: &Config, version: &KubeVersion, ip: &str) -> Flags {
+    let etcd_servers: Vec<String> = config
+        .instance_hosts
+        .keys()
+        .map(|ip| format!("https://{}:2379", networking::host(ip)))
+        .collect();
+    let mut flags = Flags::new(version);
+    flags
+        .set("etcd-servers", etcd_servers.join(","))
+        .set("bind-address", ip)
+        .set("secure-port", config.networking.apiserver_port)
+        .set("advertise-address", ip)
+        .set("allow-privileged", "true")
+        .set("service-cluster-ip-range", &config.networking.service_cidr)
+        .set(
+            "enable-admission-plugins",
+            "NamespaceLifecycle,LimitRanger,ServiceAccount,ResourceQuota,NodeRestriction",
+        )
+        .set("authorization-mode", "RBAC,Node")
+        .set("enable-bootstrap-token-auth", "true")
+        .set("token-auth-file", "/opt/kubernetes/cfg/token.csv")
+        .set(
+            "service-node-port-range",
+            &config.networking.node_port_range,
+        )
+        .set(
+            "kubelet-client-certificate",
+            "/opt/kubernetes/ssl/server.pem",
+        )
+        .set("kubelet-client-key", "/opt/kubernetes/ssl/server-key.pem")
+        .set("tls-cert-file", "/opt/kubernetes/ssl/server.pem")
+        .set("tls-private-key-file", "/opt/kubernetes/ssl/server-key.pem")
+        .set("client-ca-file", "/opt/kubernetes/ssl/ca.pem")
+        .set("service-account-key-file", "/opt/kubernetes/ssl/ca-key.pem")
+        .set("service-account-issuer", "api")
+        .set(
+            "service-account-signing-key-file",
+            "/opt/kubernetes/ssl/server-key.pem",
+        )
+        .set("etcd-cafile", "/opt/etcd/ssl/ca.pem")
+        .set("etcd-certfile", "/opt/etcd/ssl/server.pem")
+        .set("etcd-keyfile", "/opt/etcd/ssl/server-key.pem")
+        .set("requestheader-client-ca-file", "/opt/kubernetes/ssl/ca.pem")
+        .set("proxy-client-cert-file", "/opt/kubernetes/ssl/server.pem")
+        .set(
+            "proxy-client-key-file",
+            "/opt/kubernetes/ssl/server-key.pem",
+        )
+        .set("requestheader-allowed-names", "kubernetes")
+        .set("requestheader-extra-headers-prefix", "X-Remote-Extra-")
+        .set("requestheader-group-headers", "X-Remote-Group")
+        .set("requestheader-username-headers", "X-Remote-User")
+        .set("enable-aggregator-routing", "true")
+        .set("audit-log-maxage", 30)
+        .set("audit-log-maxbackup", 3)
+        .set("audit-log-maxsize", 100)
+        .set("audit-log-path", "/opt/kubernetes/logs/k8s-audit.log")
+        .extend(&config.components.kube_apiserver.extra_args);
+    flags
+}
@@ -0,0 +1,27 @@
+use crate::component_config::Flags;
+use crate::config::Config;
+use crate::version::KubeVersion;
+
+pub fn flags(config: &Config, version: &KubeVersion) -> Flags {
+    let mut flags = Flags::new(version);
+    flags
+        .set("leader-elect", "true")
+        .set(
+            "kubeconfig",
+            "/opt/kubernetes/cfg/kube-controller-manager.kubeconfig",
+        )
+        .set("bind-address", "127.0.0.1")
+        .set("allocate-node-cidrs", "true")
+        .set("cluster-cidr", &config.networking.pod_cidr)
+        .set("service-cluster-ip-range", &config.networking.service_cidr)
+        .set("cluster-signing-cert-file", "/opt/kubernetes/ssl/ca.pem")
+        .set("cluster-signing-key-file", "/opt/kubernetes/ssl/ca-key.pem")
+        .set("root-ca-file", "/opt/kubernetes/ssl/ca.pem")
+        .set(
+            "service-account-private-key-file",
+            "/opt/kubernetes/ssl/ca-key.pem",
+        )
+        .set("cluster-signing-duration", "87600h0m0s")
+        .extend(&config.components.kube_controller_manager.extra_args);
+    flags
+}
@@ -0,0 +1,113 @@
+use crate::component_config::Flags;
+use crate::config::Config;
+use crate::proxy::ProxyMode;
+use crate::version::KubeVersion;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeProxyConfiguration {
+    kind: String,
+    api_version: String,
+    bind_address: String,
+    metrics_bind_address: String,
+    client_connection: ClientConnection,
+    hostname_override: String,
+    #[serde(rename = "clusterCIDR")]
+    cluster_cidr: String,
+    mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipvs: Option<Ipvs>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    feature_gates: BTreeMap<String, bool>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClientConnection {
+    kubeconfig: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Ipvs {
+    scheduler: String,
+    #[serde(rename = "strictARP")]
+    strict_arp: bool,
+}
+
+impl KubeProxyConfiguration {
+    /// `kube-proxy-config.yml` of the node `name`.
+    pub fn from(config: &Config, version: &KubeVersion, name: &str) -> KubeProxyConfiguration {
+        let mode = ProxyMode::from(config);
+        let mut proxy = KubeProxyConfiguration {
+            kind: "KubeProxyConfiguration".to_owned(),
+            api_version: "kubeproxy.config.k8s.io/v1alpha1".to_owned(),
+            bind_address: "0.0.0.0".to_owned(),
+            metrics_bind_address: "0.0.0.0:10249".to_owned(),
+            client_connection: ClientConnection {
+                kubeconfig: "/opt/kubernetes/cfg/kube-proxy.kubeconfig".to_owned(),
+            },
+            hostname_override: name.to_owned(),
+            cluster_cidr: config.networking.pod_cidr.to_owned(),
+            mode: mode.to_string(),
+            ipvs: None,
+            feature_gates: BTreeMap::new(),
+        };
+        match mode {
+            ProxyMode::Iptables => {}
+            // Strict ARP keeps nodes from answering for addresses bound to
+            // `kube-ipvs0`, which load balancers announcing VIPs rely on.
+            ProxyMode::Ipvs => {
+                proxy.ipvs = Some(Ipvs {
+                    scheduler: config.ipvs_scheduler.to_owned(),
+                    strict_arp: true,
+                })
+            }
+            ProxyMode::Nftables => {
+                if version.has_nftables_proxy_gate() {
+                    proxy
+                        .feature_gates
+                        .insert("NFTablesProxyMode".to_owned(), true);
+                }
+            }
+        }
+        proxy
+    }
+}
+
+pub fn flags(config: &Config, version: &KubeVersion) -> Flags {
+    let mut flags = Flags::new(version);
+    flags
+        .set("config", "/opt/kubernetes/cfg/kube-proxy-config.yml")
+        .extend(&config.components.kube_proxy.extra_args);
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    fn proxy(mode: &str, version: &str) -> KubeProxyConfiguration {
+        let mut config = config::template();
+        config.proxy_mode = mode.to_owned();
+        config.kubernetes_version = version.to_owned();
+        KubeProxyConfiguration::from(&config, &KubeVersion::from(&config), "node01")
+    }
+
+    #[test]
+    fn nftables_is_gated() {
+        for version in ["v1.29.0", "v1.30.4"] {
+            let proxy = proxy("nftables", version);
+            assert_eq!(proxy.feature_gates.get("NFTablesProxyMode"), Some(&true));
+        }
+        assert!(proxy("iptables", "v1.30.4").feature_gates.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "from v1.29 on")]
+    fn nftables_needs_1_29() {
+        proxy("nftables", "v1.28.0");
+    }
+}
@@ -0,0 +1,16 @@
+use crate::component_config::Flags;
+use crate::config::Config;
+use crate::version::KubeVersion;
+
+pub fn flags(config: &Config, version: &KubeVersion) -> Flags {
+    let mut flags = Flags::new(version);
+    flags
+        .set("leader-elect", "true")
+        .set(
+            "kubeconfig",
+            "/opt/kubernetes/cfg/kube-scheduler.kubeconfig",
+        )
+        .set("bind-address", "127.0.0.1")
+        .extend(&config.components.kube_scheduler.extra_args);
+    flags
+}
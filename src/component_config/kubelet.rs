@@ -0,0 +1,199 @@
+use crate::component_config::Flags;
+use crate::config::Config;
+use crate::runtime::Runtime;
+use crate::version::KubeVersion;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeletConfiguration {
+    kind: String,
+    api_version: String,
+    address: String,
+    port: u16,
+    read_only_port: u16,
+    cgroup_driver: String,
+    #[serde(rename = "clusterDNS")]
+    cluster_dns: Vec<String>,
+    cluster_domain: String,
+    fail_swap_on: bool,
+    authentication: Authentication,
+    authorization: Authorization,
+    eviction_hard: BTreeMap<String, String>,
+    max_open_files: u64,
+    max_pods: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container_runtime_endpoint: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Authentication {
+    anonymous: Anonymous,
+    webhook: AuthenticationWebhook,
+    x509: X509,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Anonymous {
+    enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuthenticationWebhook {
+    #[serde(rename = "cacheTTL")]
+    cache_ttl: String,
+    enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct X509 {
+    #[serde(rename = "clientCAFile")]
+    client_ca_file: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Authorization {
+    mode: String,
+    webhook: AuthorizationWebhook,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuthorizationWebhook {
+    #[serde(rename = "cacheAuthorizedTTL")]
+    cache_authorized_ttl: String,
+    #[serde(rename = "cacheUnauthorizedTTL")]
+    cache_unauthorized_ttl: String,
+}
+
+impl KubeletConfiguration {
+    /// `kubelet-config.yml`, shared by every node.
+    pub fn from(config: &Config, version: &KubeVersion, runtime: Runtime) -> KubeletConfiguration {
+        KubeletConfiguration {
+            kind: "KubeletConfiguration".to_owned(),
+            api_version: "kubelet.config.k8s.io/v1beta1".to_owned(),
+            address: "0.0.0.0".to_owned(),
+            port: 10250,
+            read_only_port: 10255,
+            cgroup_driver: "systemd".to_owned(),
+            cluster_dns: vec![config.networking.dns_service_ip.to_owned()],
+            cluster_domain: config.networking.cluster_domain.to_owned(),
+            fail_swap_on: false,
+            authentication: Authentication {
+                anonymous: Anonymous { enabled: false },
+                webhook: AuthenticationWebhook {
+                    cache_ttl: "2m0s".to_owned(),
+                    enabled: true,
+                },
+                x509: X509 {
+                    client_ca_file: "/opt/kubernetes/ssl/ca.pem".to_owned(),
+                },
+            },
+            authorization: Authorization {
+                mode: "Webhook".to_owned(),
+                webhook: AuthorizationWebhook {
+                    cache_authorized_ttl: "5m0s".to_owned(),
+                    cache_unauthorized_ttl: "30s".to_owned(),
+                },
+            },
+            eviction_hard: [
+                ("imagefs.available", "15%"),
+                ("memory.available", "100Mi"),
+                ("nodefs.available", "10%"),
+                ("nodefs.inodesFree", "5%"),
+            ]
+            .iter()
+            .map(|(signal, threshold)| (signal.to_string(), threshold.to_string()))
+            .collect(),
+            max_open_files: 1000000,
+            max_pods: 110,
+            // Set by flag for older releases.
+            container_runtime_endpoint: match version.has_container_runtime_flag() {
+                true => None,
+                false => runtime.endpoint().map(str::to_owned),
+            },
+        }
+    }
+}
+
+/// Flags of the kubelet of the node `name`.
+pub fn flags(config: &Config, version: &KubeVersion, runtime: Runtime, name: &str) -> Flags {
+    let mut flags = Flags::new(version);
+    flags.set("hostname-override", name);
+    // Only dockershim reads it, the flag is gone along with it.
+    if runtime.endpoint().is_none() {
+        flags.set("network-plugin", "cni");
+    }
+    // Set in `kubelet-config.yml` for newer releases.
+    if let (Some(endpoint), true) = (runtime.endpoint(), version.has_container_runtime_flag()) {
+        flags
+            .set("container-runtime", "remote")
+            .set("container-runtime-endpoint", endpoint);
+    }
+    // Spares the sandbox image from image garbage collection.
+    flags
+        .set("pod-infra-container-image", &config.sandbox_image)
+        .set("kubeconfig", "/opt/kubernetes/cfg/kubelet.kubeconfig")
+        .set(
+            "bootstrap-kubeconfig",
+            "/opt/kubernetes/cfg/bootstrap.kubeconfig",
+        )
+        .set("config", "/opt/kubernetes/cfg/kubelet-config.yml")
+        .set("cert-dir", "/opt/kubernetes/ssl")
+        .extend(&config.components.kubelet.extra_args);
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    fn kubelet(version: &str, runtime: Runtime) -> (Flags, KubeletConfiguration) {
+        let config = config::template();
+        let version = KubeVersion::parse(version).unwrap();
+        (
+            flags(&config, &version, runtime, "node01"),
+            KubeletConfiguration::from(&config, &version, runtime),
+        )
+    }
+
+    fn has(flags: &Flags, name: &str) -> bool {
+        flags.flags.iter().any(|(flag, _)| flag == name)
+    }
+
+    #[test]
+    fn dockershim_takes_network_plugin() {
+        let (flags, _) = kubelet("v1.23.17", Runtime::Docker);
+        assert!(has(&flags, "network-plugin"));
+        assert!(!has(&flags, "container-runtime-endpoint"));
+    }
+
+    #[test]
+    fn network_plugin_is_dropped_from_1_24() {
+        let (flags, kubelet) = kubelet("v1.24.0", Runtime::Containerd);
+        assert!(!has(&flags, "network-plugin"));
+        assert!(has(&flags, "container-runtime"));
+        assert!(has(&flags, "container-runtime-endpoint"));
+        assert!(kubelet.container_runtime_endpoint.is_none());
+    }
+
+    #[test]
+    fn klog_flags_are_dropped_from_1_26() {
+        let (flags, _) = kubelet("v1.25.16", Runtime::Containerd);
+        assert!(has(&flags, "logtostderr") && has(&flags, "log-dir"));
+        let (flags, _) = kubelet("v1.26.0", Runtime::Containerd);
+        assert!(!has(&flags, "logtostderr") && !has(&flags, "log-dir"));
+    }
+
+    #[test]
+    fn runtime_endpoint_moves_to_configuration_from_1_27() {
+        let (flags, kubelet) = kubelet("v1.27.0", Runtime::Containerd);
+        assert!(!has(&flags, "container-runtime"));
+        assert!(!has(&flags, "container-runtime-endpoint"));
+        assert_eq!(
+            kubelet.container_runtime_endpoint.as_deref(),
+            Runtime::Containerd.endpoint()
+        );
+    }
+}
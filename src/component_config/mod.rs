@@ -0,0 +1,243 @@
+pub mod etcd;
+pub mod kube_apiserver;
+pub mod kube_controller_manager;
+pub mod kube_proxy;
+pub mod kube_scheduler;
+pub mod kubelet;
+
+use crate::config::{ComponentOverrides, Config, ExtraArgs};
+use crate::version::KubeVersion;
+use regex::Regex;
+use serde_yaml::Value;
+use std::fs;
+
+/// Command line flags of a component, rendered into the environment file its
+/// unit expands them from.
+#[derive(Clone, Debug, Default)]
+pub struct Flags {
+    flags: Vec<(String, String)>,
+}
+
+impl Flags {
+    /// Logging flags every component starts with.
+    pub fn new(version: &KubeVersion) -> Flags {
+        let mut flags = Flags::default();
+        for (name, value) in version.log_flags() {
+            flags.set(name, value);
+        }
+        flags
+    }
+
+    /// Set `--name=value`, replacing any earlier value while keeping its place.
+    pub fn set(&mut self, name: &str, value: impl ToString) -> &mut Flags {
+        let value = value.to_string();
+        match self.flags.iter_mut().find(|(flag, _)| flag == name) {
+            Some((_, old)) => *old = value,
+            None => self.flags.push((name.to_owned(), value)),
+        }
+        self
+    }
+
+    /// Apply the `extra_args` of a component, which win over every flag
+    /// rk8s sets.
+    pub fn extend(&mut self, extra_args: &ExtraArgs) -> &mut Flags {
+        for (name, value) in &extra_args.0 {
+            self.set(name, value);
+        }
+        self
+    }
+
+    /// Content of the environment file defining `variable`, one flag per line.
+    pub fn to_env(&self, variable: &str) -> String {
+        let flags: Vec<String> = self
+            .flags
+            .iter()
+            .map(|(name, value)| {
+                // Both would end the quoted value early.
+                format!(
+                    "--{}={}",
+                    name,
+                    value.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            })
+            .collect();
+        format!("{}=\"{}\"\n", variable, flags.join(" \\\n"))
+    }
+
+    /// Write the environment file defining `variable` to `path`.
+    pub fn write(&self, variable: &str, path: &str) {
+        fs::write(path, self.to_env(variable))
+            .unwrap_or_else(|_| panic!("Error happened when trying to write `{}`", path));
+    }
+}
+
+/// Serialize a component configuration file, merging `patch` into it.
+pub fn to_yaml<T: serde::Serialize>(config: &T, patch: &Option<Value>) -> String {
+    let mut value = serde_yaml::to_value(config)
+        .expect("Error happened when trying to serialize configuration");
+    if let Some(patch) = patch {
+        merge(&mut value, patch);
+    }
+    serde_yaml::to_string(&value).expect("Error happened when trying to serialize configuration")
+}
+
+/// Merge `patch` into `target`: mappings key by key, `null` removing a key
+/// and anything else replacing what was there.
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Mapping(target), Value::Mapping(patch)) => {
+            for (key, value) in patch {
+                match (target.get_mut(key), value) {
+                    (_, Value::Null) => {
+                        target.remove(key);
+                    }
+                    (Some(old), _) => merge(old, value),
+                    (None, _) => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+/// Refuse overrides rk8s could not apply, before touching any node.
+pub fn validate(config: &Config) {
+    let flag = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]*$").unwrap();
+    let components = &config.components;
+    let all: [(&str, &ComponentOverrides); 6] = [
+        ("etcd", &components.etcd),
+        ("kube_apiserver", &components.kube_apiserver),
+        (
+            "kube_controller_manager",
+            &components.kube_controller_manager,
+        ),
+        ("kube_scheduler", &components.kube_scheduler),
+        ("kubelet", &components.kubelet),
+        ("kube_proxy", &components.kube_proxy),
+    ];
+    for (name, overrides) in all {
+        for (arg, _) in &overrides.extra_args.0 {
+            if !flag.is_match(arg) {
+                panic!(
+                    "`{}` in `components.{}.extra_args` is not a valid flag name",
+                    arg, name
+                );
+            }
+        }
+        let Some(patch) = &overrides.config_patch else {
+            continue;
+        };
+        if name != "kubelet" && name != "kube_proxy" {
+            panic!(
+                "`components.{}.config_patch` is not supported, only kubelet and kube_proxy \
+                have a configuration file, use `extra_args` instead",
+                name
+            );
+        }
+        let Some(patch) = patch.as_mapping() else {
+            panic!("`components.{}.config_patch` has to be a mapping", name);
+        };
+        for key in ["kind", "apiVersion"] {
+            if patch.contains_key(key) {
+                panic!(
+                    "`components.{}.config_patch` cannot change `{}` of the configuration",
+                    name, key
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(source: &str) -> Value {
+        serde_yaml::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn patch_overrides_defaults() {
+        let mut target = yaml("maxPods: 110\ncgroupDriver: systemd");
+        merge(&mut target, &yaml("maxPods: 250"));
+        assert_eq!(target, yaml("maxPods: 250\ncgroupDriver: systemd"));
+    }
+
+    #[test]
+    fn nested_mappings_merge() {
+        let mut target = yaml(
+            "authentication:\n  anonymous:\n    enabled: false\n  x509:\n    clientCAFile: ca.pem",
+        );
+        merge(
+            &mut target,
+            &yaml("authentication:\n  anonymous:\n    enabled: true"),
+        );
+        assert_eq!(
+            target,
+            yaml("authentication:\n  anonymous:\n    enabled: true\n  x509:\n    clientCAFile: ca.pem")
+        );
+    }
+
+    #[test]
+    fn lists_are_replaced() {
+        let mut target = yaml("clusterDNS: [10.0.0.2, 10.0.0.3]");
+        merge(&mut target, &yaml("clusterDNS: [169.254.20.10]"));
+        assert_eq!(target, yaml("clusterDNS: [169.254.20.10]"));
+    }
+
+    #[test]
+    fn null_removes_a_key() {
+        let mut target = yaml("maxPods: 110\nfailSwapOn: false");
+        merge(
+            &mut target,
+            &yaml("failSwapOn: null\nserializeImagePulls: false"),
+        );
+        assert_eq!(target, yaml("maxPods: 110\nserializeImagePulls: false"));
+    }
+
+    #[test]
+    fn to_yaml_applies_patch() {
+        let rendered = to_yaml(
+            &yaml("kind: KubeletConfiguration\nmaxPods: 110"),
+            &Some(yaml("maxPods: 20")),
+        );
+        assert_eq!(
+            yaml(&rendered),
+            yaml("kind: KubeletConfiguration\nmaxPods: 20")
+        );
+    }
+
+    #[test]
+    fn extra_args_replace_in_place_and_append() {
+        let version = KubeVersion::parse("v1.28.0").unwrap();
+        let mut flags = Flags::new(&version);
+        flags.set("config", "a.yml").set("cert-dir", "/ssl");
+        flags.extend(&ExtraArgs(vec![
+            ("config".to_owned(), "b.yml".to_owned()),
+            ("max-pods".to_owned(), "20".to_owned()),
+        ]));
+        assert_eq!(
+            flags.to_env("ARGS"),
+            "ARGS=\"--v=2 \\\n--config=b.yml \\\n--cert-dir=/ssl \\\n--max-pods=20\"\n"
+        );
+    }
+
+    #[test]
+    fn env_values_are_escaped() {
+        let mut flags = Flags::default();
+        flags.set("label", "a\"b\\c");
+        assert_eq!(flags.to_env("ARGS"), "ARGS=\"--label=a\\\"b\\\\c\"\n");
+    }
+
+    #[test]
+    fn log_flags_follow_klog_removal() {
+        let names = |version: &str| -> Vec<String> {
+            let flags = Flags::new(&KubeVersion::parse(version).unwrap());
+            flags.flags.into_iter().map(|(name, _)| name).collect()
+        };
+        assert_eq!(names("v1.25.9"), ["logtostderr", "v", "log-dir"]);
+        assert_eq!(names("v1.26.0"), ["v"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
@@ -11,17 +12,145 @@ pub struct Config {
     // The ip address of running server.
     pub instance_ip: String,
     pub instance_hosts: HashMap<String, String>,
+    // Architecture (amd64 or arm64) of hosts, detected over ssh when not set.
+    #[serde(default)]
+    pub instance_archs: HashMap<String, String>,
 
     // Fields needed by `install cfssl` command.
     pub cfssl_url: String,
     pub cfssljson_url: String,
     pub cfsslcertinfo_url: String,
-    // Fields needed by `install etcd` command.
+    // Expected SHA-256 of the cfssl binaries, looked up upstream when unset.
+    #[serde(default)]
+    pub cfssl_sha256: Option<String>,
+    #[serde(default)]
+    pub cfssljson_sha256: Option<String>,
+    #[serde(default)]
+    pub cfsslcertinfo_sha256: Option<String>,
+    // `{version}` in the URLs below is replaced by the according version,
+    // `{arch}` by the node architecture (amd64, arm64) and `{machine}` by
+    // its `uname -m` counterpart (x86_64, aarch64).
+    // Expected SHA-256 of each archive are keyed by architecture, releases
+    // rk8s does not pin are refused when unset.
+
+    // Fields needed by `install etcd` command. Versions default to the ones
+    // configurations written before they existed pinned in their URLs.
+    #[serde(default = "default_etcd_version")]
+    pub etcd_version: String,
     pub etcd_url: String,
+    #[serde(default)]
+    pub etcd_sha256: HashMap<String, String>,
     // Fields needed by `install docker` command.
+    #[serde(default = "default_docker_version")]
+    pub docker_version: String,
     pub docker_url: String,
+    #[serde(default)]
+    pub docker_sha256: HashMap<String, String>,
+    // Container runtime kubelet uses, either `docker`, `containerd` or `crio`.
+    #[serde(default = "default_container_runtime")]
+    pub container_runtime: String,
+    // Fields needed by `install containerd` command, runc is installed along.
+    #[serde(default = "default_containerd_version")]
+    pub containerd_version: String,
+    #[serde(default = "default_containerd_url")]
+    pub containerd_url: String,
+    #[serde(default)]
+    pub containerd_sha256: HashMap<String, String>,
+    #[serde(default = "default_runc_version")]
+    pub runc_version: String,
+    #[serde(default = "default_runc_url")]
+    pub runc_url: String,
+    #[serde(default)]
+    pub runc_sha256: HashMap<String, String>,
+    // Default OCI runtime (runc, youki or crun) of every node, per node
+    // overrides being keyed by ip address.
+    #[serde(default = "default_oci_runtime")]
+    pub oci_runtime: String,
+    #[serde(default)]
+    pub oci_runtimes: HashMap<String, String>,
+    #[serde(default = "default_youki_version")]
+    pub youki_version: String,
+    #[serde(default = "default_youki_url")]
+    pub youki_url: String,
+    #[serde(default)]
+    pub youki_sha256: HashMap<String, String>,
+    #[serde(default = "default_crun_version")]
+    pub crun_version: String,
+    #[serde(default = "default_crun_url")]
+    pub crun_url: String,
+    #[serde(default)]
+    pub crun_sha256: HashMap<String, String>,
+    // Fields needed by `install crio` command, CRI-O releases follow the
+    // kubernetes minor version, which is used when `crio_version` is unset.
+    #[serde(default)]
+    pub crio_version: Option<String>,
+    #[serde(default = "default_crio_url")]
+    pub crio_url: String,
+    #[serde(default)]
+    pub crio_sha256: HashMap<String, String>,
     // Fields needed by `install kubernetes` command.
+    #[serde(default = "default_kubernetes_version")]
+    pub kubernetes_version: String,
     pub kubernetes_url: String,
+    #[serde(default)]
+    pub kubernetes_sha256: HashMap<String, String>,
+    // CNI plugins installed to `/opt/cni/bin` along with kubernetes.
+    #[serde(default = "default_cni_version")]
+    pub cni_version: String,
+    #[serde(default = "default_cni_url")]
+    pub cni_url: String,
+    #[serde(default)]
+    pub cni_sha256: HashMap<String, String>,
+
+    // Image of the pod sandbox (pause) container, used by every runtime.
+    #[serde(default = "default_sandbox_image")]
+    pub sandbox_image: String,
+    // Mirror endpoints of registries, keyed by registry host (`docker.io`).
+    #[serde(default)]
+    pub registry_mirrors: HashMap<String, Vec<String>>,
+    // Registries reached over plain HTTP or with unverified certificates.
+    #[serde(default)]
+    pub insecure_registries: Vec<String>,
+    // Credentials of private registries, keyed by registry host.
+    #[serde(default)]
+    pub registry_auths: HashMap<String, RegistryAuth>,
+    // Address ranges and ports every component is generated from.
+    #[serde(default)]
+    pub networking: Networking,
+    // How long components get to report healthy once started.
+    #[serde(default)]
+    pub readiness: Readiness,
+    // Address (`host` or `host:port`) every kubeconfig reaches the apiserver
+    // through, each master's own address being used when unset.
+    #[serde(default)]
+    pub control_plane_endpoint: Option<String>,
+    // Virtual IP kept on a healthy master by keepalived, HAProxy spreading
+    // what it receives over every apiserver.
+    #[serde(default)]
+    pub load_balancer: Option<LoadBalancer>,
+    // Workers reach the apiservers through HAProxy on their loopback
+    // address instead, for sites without a VIP.
+    #[serde(default)]
+    pub node_local_load_balancer: bool,
+    // kube-proxy mode, either `iptables`, `ipvs` or `nftables` (v1.29+).
+    #[serde(default = "default_proxy_mode")]
+    pub proxy_mode: String,
+    // Scheduler of IPVS virtual services, such as `rr`, `wrr` or `sh`.
+    #[serde(default = "default_ipvs_scheduler")]
+    pub ipvs_scheduler: String,
+    // Pod network add-on applied once the cluster is up.
+    #[serde(default)]
+    pub network_plugin: NetworkPlugin,
+    // CoreDNS serving `cluster_domain` on `dns_service_ip`.
+    #[serde(default)]
+    pub cluster_dns: ClusterDns,
+    // Manifests applied after the control plane is healthy, see `rk8s addon`.
+    #[serde(default)]
+    pub addons: Vec<Addon>,
+    // Flags and configuration file patches of each component, applied over
+    // what rk8s generates.
+    #[serde(default)]
+    pub components: Components,
 
     // Fields needed by `etcd` phase.
     pub etcd_ca_CN: String,
@@ -87,10 +216,222 @@ pub struct Config {
     pub kube_proxy_names_OU: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegistryAuth {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Networking {
+    // Range service ClusterIPs are allocated from, the first one going to
+    // the apiserver.
+    #[serde(default = "default_service_cidr")]
+    pub service_cidr: String,
+    // Range pod addresses are allocated from.
+    #[serde(default = "default_pod_cidr")]
+    pub pod_cidr: String,
+    // ClusterIP of CoreDNS, inside `service_cidr`.
+    #[serde(default = "default_dns_service_ip")]
+    pub dns_service_ip: String,
+    #[serde(default = "default_cluster_domain")]
+    pub cluster_domain: String,
+    #[serde(default = "default_node_port_range")]
+    pub node_port_range: String,
+    #[serde(default = "default_apiserver_port")]
+    pub apiserver_port: u16,
+}
+
+impl Default for Networking {
+    fn default() -> Self {
+        Networking {
+            service_cidr: default_service_cidr(),
+            pod_cidr: default_pod_cidr(),
+            dns_service_ip: default_dns_service_ip(),
+            cluster_domain: default_cluster_domain(),
+            node_port_range: default_node_port_range(),
+            apiserver_port: default_apiserver_port(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Readiness {
+    // Seconds, unless set for the component in `timeouts`.
+    #[serde(default = "default_readiness_timeout")]
+    pub timeout: u64,
+    // Seconds keyed by systemd unit, such as `etcd` or `kube-apiserver`.
+    #[serde(default)]
+    pub timeouts: HashMap<String, u64>,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Readiness {
+            timeout: default_readiness_timeout(),
+            timeouts: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoadBalancer {
+    pub vip: String,
+    // HAProxy listens on every master, next to the apiserver, so this has to
+    // differ from `apiserver_port`.
+    #[serde(default = "default_load_balancer_port")]
+    pub port: u16,
+    // Interface the VIP is added to, the one routing to it when unset.
+    #[serde(default)]
+    pub interface: Option<String>,
+    // VRRP router id, unique among keepalived instances of the network.
+    #[serde(default = "default_virtual_router_id")]
+    pub virtual_router_id: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkPlugin {
+    // Either `calico`, `flannel` or `cilium`.
+    #[serde(default = "default_network_plugin_name")]
+    pub name: String,
+}
+
+impl Default for NetworkPlugin {
+    fn default() -> Self {
+        NetworkPlugin {
+            name: default_network_plugin_name(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ClusterDns {
+    // Resolvers queries outside the cluster domain are forwarded to, those
+    // of the nodes when empty.
+    #[serde(default)]
+    pub upstream_resolvers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Components {
+    #[serde(default)]
+    pub etcd: ComponentOverrides,
+    #[serde(default)]
+    pub kube_apiserver: ComponentOverrides,
+    #[serde(default)]
+    pub kube_controller_manager: ComponentOverrides,
+    #[serde(default)]
+    pub kube_scheduler: ComponentOverrides,
+    #[serde(default)]
+    pub kubelet: ComponentOverrides,
+    #[serde(default)]
+    pub kube_proxy: ComponentOverrides,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ComponentOverrides {
+    // Flags keyed by name (`feature-gates`), replacing those rk8s sets.
+    #[serde(default)]
+    pub extra_args: ExtraArgs,
+    // Merged into the configuration file, only kubelet and kube-proxy have
+    // one. Mappings are merged key by key, `null` removes a key and any
+    // other value replaces the generated one.
+    #[serde(default)]
+    pub config_patch: Option<serde_yaml::Value>,
+}
+
+/// Flags in the order they are written, refusing any given twice.
+#[derive(Debug, Clone, Default)]
+pub struct ExtraArgs(pub Vec<(String, String)>);
+
+impl Serialize for ExtraArgs {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtraArgs {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = ExtraArgs;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of flag names to values")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<ExtraArgs, A::Error> {
+                use serde::de::Error;
+                let mut args: Vec<(String, String)> = Vec::new();
+                while let Some((name, value)) = map.next_entry::<String, serde_yaml::Value>()? {
+                    // `--v` and `v` are the same flag.
+                    let name = name.trim_start_matches('-').to_owned();
+                    let value = match value {
+                        serde_yaml::Value::String(value) => value,
+                        serde_yaml::Value::Number(value) => value.to_string(),
+                        serde_yaml::Value::Bool(value) => value.to_string(),
+                        _ => {
+                            return Err(A::Error::custom(format!(
+                                "value of flag `{}` has to be a string, number or boolean",
+                                name
+                            )))
+                        }
+                    };
+                    if args.iter().any(|(arg, _)| *arg == name) {
+                        return Err(A::Error::custom(format!(
+                            "flag `{}` is given more than once in `extra_args`",
+                            name
+                        )));
+                    }
+                    args.push((name, value));
+                }
+                Ok(ExtraArgs(args))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Addon {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Directory of manifests (or a single manifest), built-in add-ons being
+    // looked up by name when unset.
+    #[serde(default)]
+    pub path: Option<String>,
+    // Add-ons to be applied before this one.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    // Values of `{{ name }}` placeholders in manifests.
+    #[serde(default)]
+    pub vars: HashMap<String, String>,
+}
+
 impl Config {
     pub fn init() -> Config {
-        tracing::info!("Reading config file...");
-        let mut file = File::open("cfg/config.yaml").expect("File `config.yaml` does not exist!");
+        let path = workdir::config_file();
+        tracing::info!(
+            "Reading config file of cluster `{}`...",
+            workdir::cluster_name()
+        );
+        let mut file = File::open(&path).unwrap_or_else(|_| {
+            panic!(
+                "File `{}` does not exist, run `rk8s generate config` first",
+                path.display()
+            )
+        });
         let mut content = vec![];
         file.read_to_end(&mut content)
             .expect("Error happened when trying to read content of `config.yaml`");
@@ -99,12 +440,155 @@ impl Config {
         tracing::info!("Config read");
         config
     }
+
+    pub fn save(&self) {
+        let yaml = serde_yaml::to_string(self)
+            .expect("Error happened when trying to serialize `config.yaml`");
+        File::create(workdir::config_file())
+            .expect("Error happened when trying to create `config.yaml`")
+            .write_all(yaml.as_bytes())
+            .expect("Error happened when trying to write `config.yaml`");
+    }
+
+    /// The master cluster-wide manifests are applied from, the same one on
+    /// every run.
+    pub fn first_master(&self) -> (&String, &String) {
+        self.instance_hosts
+            .iter()
+            .filter(|(_, name)| name.contains("master"))
+            .min_by_key(|(ip, _)| ip.as_str())
+            .expect("No master found in `instance_hosts`")
+    }
 }
 
-pub fn generate_config_template() {
-    let config = Config {
+// Configurations predating `container_runtime` were deployed with docker.
+fn default_etcd_version() -> String {
+    "v3.4.9".to_owned()
+}
+
+fn default_docker_version() -> String {
+    "20.10.9".to_owned()
+}
+
+fn default_kubernetes_version() -> String {
+    "v1.20.15".to_owned()
+}
+
+fn default_cni_version() -> String {
+    "v1.1.1".to_owned()
+}
+
+fn default_cni_url() -> String {
+    "https://github.com/containernetworking/plugins/releases/download/{version}/cni-plugins-linux-{arch}-{version}.tgz".to_owned()
+}
+
+fn default_container_runtime() -> String {
+    "docker".to_owned()
+}
+
+fn default_containerd_version() -> String {
+    "1.7.13".to_owned()
+}
+
+fn default_containerd_url() -> String {
+    "https://github.com/containerd/containerd/releases/download/v{version}/containerd-{version}-linux-{arch}.tar.gz".to_owned()
+}
+
+fn default_runc_version() -> String {
+    "v1.1.12".to_owned()
+}
+
+fn default_runc_url() -> String {
+    "https://github.com/opencontainers/runc/releases/download/{version}/runc.{arch}".to_owned()
+}
+
+fn default_sandbox_image() -> String {
+    "registry.k8s.io/pause:3.9".to_owned()
+}
+
+fn default_proxy_mode() -> String {
+    "iptables".to_owned()
+}
+
+fn default_ipvs_scheduler() -> String {
+    "rr".to_owned()
+}
+
+fn default_network_plugin_name() -> String {
+    "calico".to_owned()
+}
+
+fn default_service_cidr() -> String {
+    "10.0.0.0/24".to_owned()
+}
+
+fn default_pod_cidr() -> String {
+    "10.244.0.0/16".to_owned()
+}
+
+fn default_dns_service_ip() -> String {
+    "10.0.0.2".to_owned()
+}
+
+fn default_cluster_domain() -> String {
+    "cluster.local".to_owned()
+}
+
+fn default_node_port_range() -> String {
+    "30000-32767".to_owned()
+}
+
+fn default_apiserver_port() -> u16 {
+    6443
+}
+
+fn default_readiness_timeout() -> u64 {
+    180
+}
+
+fn default_load_balancer_port() -> u16 {
+    8443
+}
+
+fn default_virtual_router_id() -> u8 {
+    51
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_oci_runtime() -> String {
+    "youki".to_owned()
+}
+
+fn default_youki_version() -> String {
+    "0.3.3".to_owned()
+}
+
+fn default_youki_url() -> String {
+    "https://github.com/containers/youki/releases/download/v{version}/youki-{version}-{machine}-gnu.tar.gz".to_owned()
+}
+
+fn default_crun_version() -> String {
+    "1.14.4".to_owned()
+}
+
+fn default_crun_url() -> String {
+    "https://github.com/containers/crun/releases/download/{version}/crun-{version}-linux-{arch}"
+        .to_owned()
+}
+
+fn default_crio_url() -> String {
+    "https://storage.googleapis.com/cri-o/artifacts/cri-o.{arch}.{version}.tar.gz".to_owned()
+}
+
+/// Configuration written by `rk8s generate config`.
+pub fn template() -> Config {
+    Config {
         instance_name: "master01".to_owned(),
         instance_ip: "192.168.157.130".to_owned(),
+        instance_archs: HashMap::new(),
         instance_hosts: {
             let mut map = HashMap::new();
             map.insert(
@@ -117,9 +601,61 @@ pub fn generate_config_template() {
         cfssl_url: "https://pkg.cfssl.org/R1.2/cfssl_linux-amd64".to_owned(),
         cfssljson_url: "https://pkg.cfssl.org/R1.2/cfssljson_linux-amd64".to_owned(),
         cfsslcertinfo_url: "https://pkg.cfssl.org/R1.2/cfssl-certinfo_linux-amd64".to_owned(),
-        etcd_url: "https://github.com/etcd-io/etcd/releases/download/v3.4.9/etcd-v3.4.9-linux-amd64.tar.gz".to_owned(),
-        docker_url: "https://download.docker.com/linux/static/stable/x86_64/docker-20.10.9.tgz".to_owned(),
-        kubernetes_url: "https://dl.k8s.io/v1.20.15/kubernetes-server-linux-amd64.tar.gz".to_owned(),
+        etcd_version: default_etcd_version(),
+        etcd_url: "https://github.com/etcd-io/etcd/releases/download/{version}/etcd-{version}-linux-{arch}.tar.gz".to_owned(),
+        docker_version: default_docker_version(),
+        docker_url: "https://download.docker.com/linux/static/stable/{machine}/docker-{version}.tgz".to_owned(),
+        container_runtime: "containerd".to_owned(),
+        containerd_version: default_containerd_version(),
+        containerd_url: default_containerd_url(),
+        runc_version: default_runc_version(),
+        runc_url: default_runc_url(),
+        oci_runtime: default_oci_runtime(),
+        oci_runtimes: HashMap::new(),
+        youki_version: default_youki_version(),
+        youki_url: default_youki_url(),
+        crun_version: default_crun_version(),
+        crun_url: default_crun_url(),
+        crio_version: None,
+        crio_url: default_crio_url(),
+        kubernetes_version: default_kubernetes_version(),
+        kubernetes_url: "https://dl.k8s.io/{version}/kubernetes-server-linux-{arch}.tar.gz".to_owned(),
+        cni_version: default_cni_version(),
+        cni_url: default_cni_url(),
+        cfssl_sha256: None,
+        cfssljson_sha256: None,
+        cfsslcertinfo_sha256: None,
+        etcd_sha256: HashMap::new(),
+        docker_sha256: HashMap::new(),
+        containerd_sha256: HashMap::new(),
+        runc_sha256: HashMap::new(),
+        youki_sha256: HashMap::new(),
+        crun_sha256: HashMap::new(),
+        crio_sha256: HashMap::new(),
+        kubernetes_sha256: HashMap::new(),
+        cni_sha256: HashMap::new(),
+
+        sandbox_image: default_sandbox_image(),
+        registry_mirrors: HashMap::new(),
+        insecure_registries: Vec::new(),
+        registry_auths: HashMap::new(),
+        networking: Networking::default(),
+        readiness: Readiness::default(),
+        control_plane_endpoint: None,
+        load_balancer: None,
+        node_local_load_balancer: false,
+        proxy_mode: default_proxy_mode(),
+        ipvs_scheduler: default_ipvs_scheduler(),
+        network_plugin: NetworkPlugin::default(),
+        cluster_dns: ClusterDns::default(),
+        addons: vec![Addon {
+            name: "metrics-server".to_owned(),
+            enabled: true,
+            path: None,
+            depends_on: Vec::new(),
+            vars: HashMap::new(),
+        }],
+        components: Components::default(),
 
         etcd_ca_CN: "etcd CA".to_owned(),
         etcd_CN: "etcd".to_owned(),
@@ -187,9 +723,123 @@ pub fn generate_config_template() {
         kube_proxy_names_ST: "Beijing".to_owned(),
         kube_proxy_names_O: "k8s".to_owned(),
         kube_proxy_names_OU: "System".to_owned(),
-    };
+    }
+}
 
+pub fn generate_config_template() {
+    let config = template();
     let yaml = serde_yaml::to_string(&config).unwrap();
-    let mut file = File::create("cfg/config.yaml").unwrap();
+    let mut file = File::create(workdir::config_file()).unwrap();
     file.write_all(yaml.as_bytes()).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extra_args(source: &str) -> Result<ExtraArgs, serde_yaml::Error> {
+        serde_yaml::from_str(source)
+    }
+
+    #[test]
+    fn extra_args_strip_dashes_and_stringify() {
+        let args = extra_args("--v: 4\nmax-pods: 20\nprofiling: false").unwrap();
+        assert_eq!(
+            args.0,
+            [("v", "4"), ("max-pods", "20"), ("profiling", "false")]
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        );
+    }
+
+    #[test]
+    fn extra_args_refuse_duplicates() {
+        let error = extra_args("v: 4\n--v: 5").unwrap_err();
+        assert!(error.to_string().contains("more than once"));
+    }
+
+    // Written by `rk8s generate config` before versions were configurable.
+    const LEGACY: &str = r#"instance_name: master01
+instance_ip: 192.168.157.130
+instance_hosts:
+  192.168.157.130: master01
+cfssl_url: https://pkg.cfssl.org/R1.2/cfssl_linux-amd64
+cfssljson_url: https://pkg.cfssl.org/R1.2/cfssljson_linux-amd64
+cfsslcertinfo_url: https://pkg.cfssl.org/R1.2/cfssl-certinfo_linux-amd64
+etcd_url: https://github.com/etcd-io/etcd/releases/download/v3.4.9/etcd-v3.4.9-linux-amd64.tar.gz
+docker_url: https://download.docker.com/linux/static/stable/x86_64/docker-20.10.9.tgz
+kubernetes_url: https://dl.k8s.io/v1.20.15/kubernetes-server-linux-amd64.tar.gz
+etcd_ca_CN: etcd CA
+etcd_CN: etcd
+etcd_key_algo: rsa
+etcd_key_size: 2048
+etcd_expiry: 87600h
+etcd_usages:
+- signing
+- key encipherment
+- server auth
+- client auth
+etcd_names_C: CN
+etcd_names_L: Beijing
+etcd_names_ST: Beijing
+kube_apiserver_CN: kubernetes
+kube_apiserver_key_algo: rsa
+kube_apiserver_key_size: 2048
+kube_apiserver_expiry: 87600h
+kube_apiserver_usages:
+- signing
+- key encipherment
+- server auth
+- client auth
+kube_apiserver_names_C: CN
+kube_apiserver_names_L: Beijing
+kube_apiserver_names_ST: Beijing
+kube_apiserver_names_O: k8s
+kube_apiserver_names_OU: System
+kube_controller_manager_CN: system:kube-controller-manager
+kube_controller_manager_key_algo: rsa
+kube_controller_manager_key_size: 2048
+kube_controller_manager_names_C: CN
+kube_controller_manager_names_L: Beijing
+kube_controller_manager_names_ST: Beijing
+kube_controller_manager_names_O: system:masters
+kube_controller_manager_names_OU: System
+kube_scheduler_CN: system:kube-scheduler
+kube_scheduler_key_algo: rsa
+kube_scheduler_key_size: 2048
+kube_scheduler_names_C: CN
+kube_scheduler_names_L: Beijing
+kube_scheduler_names_ST: Beijing
+kube_scheduler_names_O: system:masters
+kube_scheduler_names_OU: System
+kube_ctl_CN: admin
+kube_ctl_key_algo: rsa
+kube_ctl_key_size: 2048
+kube_ctl_names_C: CN
+kube_ctl_names_L: Beijing
+kube_ctl_names_ST: Beijing
+kube_ctl_names_O: system:masters
+kube_ctl_names_OU: System
+kube_proxy_CN: system:kube-proxy
+kube_proxy_key_algo: rsa
+kube_proxy_key_size: 2048
+kube_proxy_names_C: CN
+kube_proxy_names_L: Beijing
+kube_proxy_names_ST: Beijing
+kube_proxy_names_O: k8s
+kube_proxy_names_OU: System
+"#;
+
+    #[test]
+    fn legacy_configuration_loads() {
+        let config: Config = serde_yaml::from_str(LEGACY).unwrap();
+        let template = template();
+        assert_eq!(config.etcd_version, template.etcd_version);
+        assert_eq!(config.docker_version, template.docker_version);
+        assert_eq!(config.kubernetes_version, template.kubernetes_version);
+        assert_eq!(config.cni_version, template.cni_version);
+        assert_eq!(config.cni_url, template.cni_url);
+        // The version they pinned in their URLs.
+        assert!(config.kubernetes_url.contains(&config.kubernetes_version));
+        assert!(config.etcd_url.contains(&config.etcd_version));
+    }
+}
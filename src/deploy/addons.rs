@@ -0,0 +1,18 @@
+use crate::addon;
+use crate::config::Config;
+
+pub fn start(config: &Config) {
+    let enabled: Vec<String> = config
+        .addons
+        .iter()
+        .filter(|addon| addon.enabled)
+        .map(|addon| addon.name.to_owned())
+        .collect();
+    if enabled.is_empty() {
+        return;
+    }
+    addon::wait_control_plane(config);
+    for addon in addon::ordered(config, &enabled) {
+        addon::apply(config, &addon);
+    }
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use crate::cni::Cni;
+use crate::config::Config;
+use crate::networking;
+use crate::workdir;
+
+pub fn start(config: &Config) {
+    // The manifest is applied once against the cluster, from the first master.
+    let cni = Cni::from(config);
+    tracing::info!("Deploying {} network plugin...", cni);
+    tracing::info!("Change working directory into `k8s`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    let (ip, name) = config.first_master();
+
+    tracing::info!("Generating `cni.yaml` to to_send/...");
+    fs::write("to_send/cni.yaml", cni.manifest(config))
+        .expect("Error happened when trying to write `cni.yaml`");
+    tracing::info!("`cni.yaml` generated");
+
+    Command::new("scp")
+        .arg("to_send/cni.yaml")
+        .arg(format!(
+            "root@{}:/opt/kubernetes/cfg/",
+            networking::host(ip)
+        ))
+        .status()
+        .expect("Error happened when trying to send files to other nodes");
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg("kubectl apply -f /opt/kubernetes/cfg/cni.yaml")
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!("Failed to apply {} manifest from {} ({})", cni, name, ip);
+    }
+
+    tracing::info!("Waiting for {} pods to become ready...", cni);
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg(cni.wait_command())
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        let (namespace, _) = cni.daemonset();
+        panic!(
+            "{} pods did not become ready, please check `kubectl -n {} get pods` on {}",
+            cni, namespace, name
+        );
+    }
+    tracing::info!("{} is ready", cni);
+
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::arch::{self, Arch};
+use crate::artifact::Artifact;
+use crate::config::Config;
+use crate::networking;
+use crate::oci::OciRuntime;
+use crate::registry;
+use crate::workdir;
+
+struct ContainerdCfg;
+
+impl ContainerdCfg {
+    fn generate(current_ip: &String, runtime: OciRuntime, config: &Config) {
+        // Send to /etc/containerd/config.toml
+        check_dir_exist_or_create(PathBuf::from(format!("to_send/{}", current_ip)));
+        let mut containerd_cfg = File::create(format!("to_send/{}/config.toml", current_ip))
+            .expect("Error happened when trying to create containerd config file");
+        // Youki and crun are driven by the runc shim under another binary name.
+        let runtimes = match runtime.path() {
+            Some(path) => format!(
+                r#"
+      [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.{0}]
+        runtime_type = "io.containerd.runc.v2"
+        [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.{0}.options]
+          BinaryName = "{1}"
+          SystemdCgroup = true"#,
+                runtime, path
+            ),
+            None => String::new(),
+        };
+        // Kubelet is set to the systemd cgroup driver, runc has to follow.
+        let content = format!(
+            r#"version = 2
+
+[plugins]
+  [plugins."io.containerd.grpc.v1.cri"]
+    sandbox_image = "{}"
+    [plugins."io.containerd.grpc.v1.cri".containerd]
+      default_runtime_name = "{}"
+      [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.runc]
+        runtime_type = "io.containerd.runc.v2"
+        [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.runc.options]
+          SystemdCgroup = true{}
+    [plugins."io.containerd.grpc.v1.cri".cni]
+      bin_dir = "/opt/cni/bin"
+      conf_dir = "/etc/cni/net.d"
+{}"#,
+            config.sandbox_image,
+            runtime,
+            runtimes,
+            registry::containerd_registry_toml(config)
+        );
+        containerd_cfg
+            .write_all(content.as_bytes())
+            .expect("Error happened when trying to write containerd config file");
+    }
+}
+
+struct ContainerdUnit;
+
+impl ContainerdUnit {
+    fn generate() {
+        let mut containerd_unit = File::create("to_send/containerd.service")
+            .expect("Error happened when trying to create containerd unit file");
+        let content = r#"[Unit]
+Description=containerd container runtime
+Documentation=https://containerd.io
+After=network.target local-fs.target
+
+[Service]
+ExecStartPre=-/sbin/modprobe overlay
+ExecStart=/usr/bin/containerd
+Type=notify
+Delegate=yes
+KillMode=process
+Restart=always
+RestartSec=5
+LimitNPROC=infinity
+LimitCORE=infinity
+LimitNOFILE=infinity
+TasksMax=infinity
+OOMScoreAdjust=-999
+
+[Install]
+WantedBy=multi-user.target
+"#;
+        containerd_unit
+            .write_all(content.as_bytes())
+            .expect("Error happened when trying to write containerd unit file");
+    }
+}
+
+pub fn start(config: &Config) {
+    // Deploy containerd and runc to all hosts.
+    // Containerd does not distinguish masters or workers.
+    tracing::info!("Preparing mutual .toml, .service and containerd binaries...");
+    tracing::info!("Change working directory into `containerd`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::staging("containerd");
+    check_dir_exist_or_create(work_dir.to_path_buf());
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `containerd`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    // Prepare directory to be sent.
+    let path = PathBuf::from("to_send/");
+    check_dir_exist_or_create(path);
+
+    // Binaries are prepared once per architecture found among nodes.
+    let archs = arch::of_hosts(config);
+    let mut binaries = HashMap::new();
+    let mut runc = HashMap::new();
+    for arch in arch::distinct(&archs) {
+        let containerd = Artifact::containerd(config, arch);
+        let tarball = containerd.stage();
+
+        tracing::info!("untaring containerd {} binaries...", arch);
+        check_dir_exist_or_create(PathBuf::from(arch.as_str()));
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(arch.as_str())
+            .status()
+            .expect("Error happened when trying to untar `containerd` executable");
+        let arch_binaries: Vec<PathBuf> =
+            fs::read_dir(PathBuf::from(arch.as_str()).join(&containerd.dir))
+                .expect("Error happened when trying to read untared `containerd` folder")
+                .flatten()
+                .map(|entry| entry.path())
+                .collect();
+        binaries.insert(arch, arch_binaries);
+        runc.insert(arch, Artifact::runc(config, arch).stage());
+    }
+
+    tracing::info!("Generating registry hosts to to_send/certs.d/");
+    let certs = PathBuf::from("to_send/certs.d");
+    if certs.is_dir() {
+        fs::remove_dir_all(&certs).expect("Error happened when trying to clean `certs.d`");
+    }
+    for host in registry::hosts(config) {
+        check_dir_exist_or_create(certs.join(&host));
+        fs::write(
+            certs.join(&host).join("hosts.toml"),
+            registry::containerd_hosts_toml(config, &host),
+        )
+        .expect("Error happened when trying to write `hosts.toml`");
+    }
+    tracing::info!("registry hosts generated");
+
+    tracing::info!("Generating containerd.service file to to_send/");
+    ContainerdUnit::generate();
+    tracing::info!("containerd.service generated");
+
+    for (ip, name) in &config.instance_hosts {
+        tracing::info!("Found instance {} on {},", name, ip);
+
+        Command::new("scp")
+            .args(&binaries[&archs[ip]])
+            .arg(format!("root@{}:/usr/bin", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        // Runc is released under an architecture suffixed name.
+        Command::new("scp")
+            .arg(&runc[&archs[ip]])
+            .arg(format!("root@{}:/usr/bin/runc", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("chmod +x /usr/bin/runc && mkdir -p /etc/containerd")
+            .status()
+            .expect("Error happened when trying to create config directory on other nodes");
+
+        tracing::info!("Generating config.toml to to_send/{}/", ip);
+        ContainerdCfg::generate(ip, OciRuntime::of(config, ip), config);
+        tracing::info!("config.toml generated");
+        Command::new("scp")
+            .arg(format!("to_send/{}/config.toml", ip))
+            .arg(format!("root@{}:/etc/containerd", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+
+        if certs.is_dir() {
+            Command::new("scp")
+                .arg("-r")
+                .arg(&certs)
+                .arg(format!("root@{}:/etc/containerd/", networking::host(ip)))
+                .status()
+                .expect("Error happened when trying to send files to other nodes");
+        }
+
+        Command::new("scp")
+            .arg("to_send/containerd.service")
+            .arg(format!(
+                "root@{}:/usr/lib/systemd/system/",
+                networking::host(ip)
+            ))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+
+        tracing::info!("Containerd installed on {}, starting...", name);
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl daemon-reload")
+            .status()
+            .expect("Error happened when trying to reload systemd daemons");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl start containerd")
+            .status()
+            .expect("Error happened when trying to start containerd");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl enable containerd")
+            .status()
+            .expect("Error happened when trying to enable containerd");
+        tracing::info!("Containerd started on {}", ip);
+
+        load_images(ip, archs[ip]);
+    }
+
+    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `rk8s`");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
+
+// Load container images imported from an offline bundle, if any, into the
+// namespace kubelet pulls into.
+fn load_images(ip: &String, arch: Arch) {
+    let Ok(images) = fs::read_dir(workdir::preparation().join("images").join(arch.as_str())) else {
+        return;
+    };
+    for image in images.flatten() {
+        let path = image.path();
+        tracing::info!("Loading {} on {}...", path.display(), ip);
+        Command::new("scp")
+            .arg(&path)
+            .arg(format!("root@{}:/tmp/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(format!(
+                "ctr -n k8s.io images import /tmp/{0} && rm -f /tmp/{0}",
+                image.file_name().to_string_lossy()
+            ))
+            .status()
+            .expect("Error happened when trying to load images");
+    }
+}
+
+fn check_dir_exist_or_create(path: PathBuf) {
+    if !path.is_dir() {
+        fs::create_dir_all(path).expect("Error happened when trying to create path");
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::dns;
+use crate::networking;
+use crate::workdir;
+
+pub fn start(config: &Config) {
+    // Applied once against the cluster, like the network plugin it relies on.
+    tracing::info!("Deploying CoreDNS...");
+    tracing::info!("Change working directory into `k8s`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    let (ip, name) = config.first_master();
+
+    tracing::info!("Generating `coredns.yaml` to to_send/...");
+    fs::write("to_send/coredns.yaml", dns::manifest(config))
+        .expect("Error happened when trying to write `coredns.yaml`");
+    tracing::info!("`coredns.yaml` generated");
+
+    Command::new("scp")
+        .arg("to_send/coredns.yaml")
+        .arg(format!(
+            "root@{}:/opt/kubernetes/cfg/",
+            networking::host(ip)
+        ))
+        .status()
+        .expect("Error happened when trying to send files to other nodes");
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg("kubectl apply -f /opt/kubernetes/cfg/coredns.yaml")
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!("Failed to apply CoreDNS manifest from {} ({})", name, ip);
+    }
+
+    tracing::info!("Waiting for CoreDNS pods to become ready...");
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg(dns::WAIT_COMMAND)
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!(
+            "CoreDNS pods did not become ready, please check `kubectl -n kube-system get pods` on {}",
+            name
+        );
+    }
+
+    tracing::info!("Resolving `kubernetes.default` from a test pod...");
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg(dns::test_command(config))
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!(
+            "`kubernetes.default` could not be resolved through {}, \
+            please check `kubectl -n kube-system logs deployment/coredns` on {}",
+            config.networking.dns_service_ip, name
+        );
+    }
+    tracing::info!("CoreDNS is ready");
+
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
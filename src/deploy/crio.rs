@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::arch;
+use crate::artifact::Artifact;
+use crate::config::Config;
+use crate::networking;
+use crate::oci::OciRuntime;
+use crate::registry;
+use crate::workdir;
+
+struct CrioCfg;
+
+impl CrioCfg {
+    fn generate(current_ip: &String, runtime: OciRuntime, config: &Config) {
+        // Send to /etc/crio/crio.conf.d/, overriding the defaults of the bundle.
+        check_dir_exist_or_create(PathBuf::from(format!("to_send/{}", current_ip)));
+        let mut crio_cfg = File::create(format!("to_send/{}/10-rk8s.conf", current_ip))
+            .expect("Error happened when trying to create cri-o config file");
+        let runtimes = match runtime.path() {
+            Some(path) => format!(
+                r#"
+[crio.runtime.runtimes.{}]
+runtime_path = "{}"
+runtime_type = "oci"
+"#,
+                runtime, path
+            ),
+            None => String::new(),
+        };
+        // Credentials are shared with kubelet, in the same format.
+        let auth_file = match registry::auth_json(config) {
+            Some(_) => "global_auth_file = \"/etc/crio/auth.json\"\n",
+            None => "",
+        };
+        let content = format!(
+            r#"[crio.runtime]
+cgroup_manager = "systemd"
+conmon_cgroup = "pod"
+default_runtime = "{}"
+
+[crio.runtime.runtimes.runc]
+runtime_path = "/usr/local/bin/runc"
+runtime_type = "oci"
+{}
+[crio.image]
+pause_image = "{}"
+{}
+[crio.network]
+network_dir = "/etc/cni/net.d/"
+plugin_dirs = ["/opt/cni/bin/"]
+"#,
+            runtime, runtimes, config.sandbox_image, auth_file
+        );
+        crio_cfg
+            .write_all(content.as_bytes())
+            .expect("Error happened when trying to write cri-o config file");
+    }
+}
+
+struct RegistriesCfg;
+
+impl RegistriesCfg {
+    fn generate(config: &Config) {
+        // Send to /etc/containers/registries.conf.d/
+        let mut registries_cfg = File::create("to_send/10-rk8s-registries.conf")
+            .expect("Error happened when trying to create registries config file");
+        registries_cfg
+            .write_all(registry::containers_registries_conf(config).as_bytes())
+            .expect("Error happened when trying to write registries config file");
+    }
+}
+
+pub fn start(config: &Config) {
+    // Deploy CRI-O to all hosts from its static bundle, which carries conmon,
+    // runc and crictl along.
+    // CRI-O does not distinguish masters or workers.
+    tracing::info!("Preparing mutual .conf and cri-o bundles...");
+    tracing::info!("Change working directory into `crio`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::staging("crio");
+    check_dir_exist_or_create(work_dir.to_path_buf());
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `crio`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    // Prepare directory to be sent.
+    let path = PathBuf::from("to_send/");
+    check_dir_exist_or_create(path);
+
+    // Bundles are prepared once per architecture found among nodes.
+    let archs = arch::of_hosts(config);
+    let mut bundles = HashMap::new();
+    for arch in arch::distinct(&archs) {
+        let crio = Artifact::crio(config, arch);
+        let tarball = crio.stage();
+
+        tracing::info!("untaring cri-o {} bundle...", arch);
+        check_dir_exist_or_create(PathBuf::from(arch.as_str()));
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(arch.as_str())
+            .status()
+            .expect("Error happened when trying to untar `cri-o` bundle");
+        bundles.insert(arch, PathBuf::from(arch.as_str()).join(&crio.dir));
+    }
+
+    tracing::info!("Generating 10-rk8s-registries.conf to to_send/");
+    RegistriesCfg::generate(config);
+    tracing::info!("10-rk8s-registries.conf generated");
+
+    let auths = registry::auth_json(config);
+    if let Some(auths) = &auths {
+        fs::write("to_send/auth.json", auths)
+            .expect("Error happened when trying to write cri-o `auth.json`");
+        tracing::info!("auth.json generated");
+    }
+
+    for (ip, name) in &config.instance_hosts {
+        tracing::info!("Found instance {} on {},", name, ip);
+
+        Command::new("scp")
+            .arg("-r")
+            .arg(&bundles[&archs[ip]])
+            .arg(format!("root@{}:/tmp/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        // Newer bundles ship an `install` script, older ones a Makefile.
+        // The bridge network the bundle installs would shadow the cluster CNI.
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(
+                "cd /tmp/cri-o && (if [ -x install ]; then ./install; else make install; fi) \
+                && rm -f /etc/cni/net.d/*crio-bridge* \
+                && mkdir -p /etc/crio/crio.conf.d /etc/containers/registries.conf.d \
+                && rm -rf /tmp/cri-o",
+            )
+            .status()
+            .expect("Error happened when trying to install cri-o on other nodes");
+
+        tracing::info!("Generating 10-rk8s.conf to to_send/{}/", ip);
+        CrioCfg::generate(ip, OciRuntime::of(config, ip), config);
+        tracing::info!("10-rk8s.conf generated");
+        Command::new("scp")
+            .arg(format!("to_send/{}/10-rk8s.conf", ip))
+            .arg(format!(
+                "root@{}:/etc/crio/crio.conf.d/",
+                networking::host(ip)
+            ))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("scp")
+            .arg("to_send/10-rk8s-registries.conf")
+            .arg(format!(
+                "root@{}:/etc/containers/registries.conf.d/",
+                networking::host(ip)
+            ))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+
+        if auths.is_some() {
+            Command::new("scp")
+                .arg("to_send/auth.json")
+                .arg(format!("root@{}:/etc/crio/", networking::host(ip)))
+                .status()
+                .expect("Error happened when trying to send files to other nodes");
+        }
+
+        tracing::info!("CRI-O installed on {}, starting...", name);
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl daemon-reload")
+            .status()
+            .expect("Error happened when trying to reload systemd daemons");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl start crio")
+            .status()
+            .expect("Error happened when trying to start cri-o");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl enable crio")
+            .status()
+            .expect("Error happened when trying to enable cri-o");
+        tracing::info!("CRI-O started on {}", ip);
+    }
+
+    if workdir::preparation().join("images").is_dir() {
+        tracing::warn!(
+            "Images from offline bundles can not be loaded into CRI-O, \
+            nodes will pull them from their registries"
+        );
+    }
+
+    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `rk8s`");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
+
+fn check_dir_exist_or_create(path: PathBuf) {
+    if !path.is_dir() {
+        fs::create_dir_all(path).expect("Error happened when trying to create path");
+    }
+}
@@ -1,35 +1,66 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::process::Command;
 
+use crate::arch::{self, Arch};
+use crate::artifact::Artifact;
 use crate::config::Config;
+use crate::networking;
+use crate::oci::OciRuntime;
+use crate::workdir;
 
 struct DockerCfg;
 
 impl DockerCfg {
-    fn generate() {
+    fn generate(current_ip: &String, runtime: OciRuntime, config: &Config) {
         // Send to /etc/docker/daemon.json
-        let mut docker_cfg = File::create("to_send/daemon.json")
+        check_dir_exist_or_create(PathBuf::from(format!("to_send/{}", current_ip)));
+        let mut docker_cfg = File::create(format!("to_send/{}/daemon.json", current_ip))
             .expect("Error happened when trying to create docker config file");
-        // Set youki (pre-built) as the default runtime.
-        let content = r#"{
-    "default-runtime": "youki",
-    "runtimes": {
-        "youki": {
-            "path": "/usr/bin/youki"
+        // Runc is known to docker already, others are registered by path.
+        let runtimes = match runtime.path() {
+            Some(path) => format!(
+                r#"
+    "runtimes": {{
+        "{}": {{
+            "path": "{}"
+        }}
+    }},"#,
+                runtime, path
+            ),
+            None => String::new(),
+        };
+        // Docker only takes mirrors of Docker Hub.
+        let mut registries = String::new();
+        if let Some(mirrors) = config.registry_mirrors.get("docker.io") {
+            registries.push_str(&format!(
+                "\n    \"registry-mirrors\": {},",
+                serde_json::to_string(mirrors).unwrap()
+            ));
+        }
+        if !config.insecure_registries.is_empty() {
+            registries.push_str(&format!(
+                "\n    \"insecure-registries\": {},",
+                serde_json::to_string(&config.insecure_registries).unwrap()
+            ));
         }
-    },
+        let content = format!(
+            r#"{{
+    "default-runtime": "{}",{}{}
     "exec-opts": ["native.cgroupdriver=systemd"],
     "log-driver": "json-file",
-    "log-opts": {
+    "log-opts": {{
         "max-size": "100m"
-    },
+    }},
     "storage-driver": "overlay2"
-}
-"#;
+}}
+"#,
+            runtime, runtimes, registries
+        );
         docker_cfg
             .write_all(content.as_bytes())
             .expect("Error happened when trying to write docker unit file");
@@ -76,8 +107,8 @@ pub fn start(config: &Config) {
     // Docker does not distinguish masters or workers.
     tracing::info!("Preparing mutual .json, .service and docker binaries...");
     tracing::info!("Change working directory into `docker`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/docker");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::staging("docker");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `etcd`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -85,37 +116,50 @@ pub fn start(config: &Config) {
     let path = PathBuf::from("to_send/");
     check_dir_exist_or_create(path);
 
-    tracing::info!("untaring docker binaries...");
-    Command::new("tar")
-        .arg("-zxf")
-        .arg("/rk8s/preparation/docker-20.10.9.tgz")
-        .status()
-        .expect("Error happened when trying to untar `docker` executable");
+    // Binaries are prepared once per architecture found among nodes.
+    let archs = arch::of_hosts(config);
+    let mut binaries = HashMap::new();
+    for arch in arch::distinct(&archs) {
+        let docker = Artifact::docker(config, arch);
+        let tarball = docker.stage();
+
+        tracing::info!("untaring docker {} binaries...", arch);
+        check_dir_exist_or_create(PathBuf::from(arch.as_str()));
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(arch.as_str())
+            .status()
+            .expect("Error happened when trying to untar `docker` executable");
+        // The set of binaries shipped varies between docker releases.
+        let arch_binaries: Vec<PathBuf> =
+            fs::read_dir(PathBuf::from(arch.as_str()).join(&docker.dir))
+                .expect("Error happened when trying to read untared `docker` folder")
+                .flatten()
+                .map(|entry| entry.path())
+                .collect();
+        binaries.insert(arch, arch_binaries);
+    }
 
     tracing::info!("Generating docker.service file to to_send/");
     DockerUnit::generate();
     tracing::info!("docker.service generated");
 
-    tracing::info!("Generating daemon.json to to_send/");
-    DockerCfg::generate();
-    tracing::info!("daemon.json generated");
+    if config
+        .registry_mirrors
+        .keys()
+        .any(|host| host != "docker.io")
+    {
+        tracing::warn!("Docker only supports mirrors of `docker.io`, other mirrors are ignored");
+    }
 
     for (ip, name) in &config.instance_hosts {
         tracing::info!("Found instance {} on {},", name, ip);
 
         Command::new("scp")
-            .arg("docker/containerd")
-            .arg("docker/containerd-shim")
-            .arg("docker/containerd-shim-runc-v2")
-            .arg("docker/ctr")
-            .arg("docker/docker")
-            .arg("docker/dockerd")
-            .arg("docker/docker-init")
-            .arg("docker/docker-proxy")
-            .arg("docker/runc")
-            // Send youki along with the process
-            .arg("/rk8s/preparation/youki")
-            .arg(format!("root@{}:/usr/bin", ip))
+            .args(&binaries[&archs[ip]])
+            .arg(format!("root@{}:/usr/bin", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
@@ -125,15 +169,21 @@ pub fn start(config: &Config) {
             .status()
             .expect("Error happened when trying to create config directory on other nodes");
 
+        tracing::info!("Generating daemon.json to to_send/{}/", ip);
+        DockerCfg::generate(ip, OciRuntime::of(config, ip), config);
+        tracing::info!("daemon.json generated");
         Command::new("scp")
-            .arg("to_send/daemon.json")
-            .arg(format!("root@{}:/etc/docker", ip))
+            .arg(format!("to_send/{}/daemon.json", ip))
+            .arg(format!("root@{}:/etc/docker", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
         Command::new("scp")
             .arg("to_send/docker.service")
-            .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+            .arg(format!(
+                "root@{}:/usr/lib/systemd/system/",
+                networking::host(ip)
+            ))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
@@ -154,6 +204,8 @@ pub fn start(config: &Config) {
             .status()
             .expect("Error happened when trying to enable docker");
         tracing::info!("Docker started on {}", ip);
+
+        load_images(ip, archs[ip]);
     }
 
     env::set_current_dir(prev_dir).expect("Error happened when trying to change into `etcd`");
@@ -163,6 +215,30 @@ pub fn start(config: &Config) {
     );
 }
 
+// Load container images imported from an offline bundle, if any.
+fn load_images(ip: &String, arch: Arch) {
+    let Ok(images) = fs::read_dir(workdir::preparation().join("images").join(arch.as_str())) else {
+        return;
+    };
+    for image in images.flatten() {
+        let path = image.path();
+        tracing::info!("Loading {} on {}...", path.display(), ip);
+        Command::new("scp")
+            .arg(&path)
+            .arg(format!("root@{}:/tmp/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(format!(
+                "docker load -i /tmp/{0} && rm -f /tmp/{0}",
+                image.file_name().to_string_lossy()
+            ))
+            .status()
+            .expect("Error happened when trying to load images");
+    }
+}
+
 fn check_dir_exist_or_create(path: PathBuf) {
     if !path.is_dir() {
         fs::create_dir_all(path).expect("Error happened when trying to create path");
@@ -1,9 +1,16 @@
+use crate::arch;
+use crate::artifact::Artifact;
+use crate::component_config::etcd::EtcdConfig;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::networking;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::thread;
 
@@ -127,60 +134,9 @@ impl ServerCsr {
 struct ETCDCfg;
 
 impl ETCDCfg {
-    fn generate(current_ip: &String, current_name: &String, config: &Config) {
-        let mut etcd_conf = File::create(format!("to_send/{}/etcd.conf", current_ip))
-            .expect("Error happened when trying to create etcd configuration file");
-
-        writeln!(&mut etcd_conf, "#[Member]")
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf, "ETCD_NAME=\"etcd_{}\"", current_name)
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_DATA_DIR=\"/var/lib/etcd/default.etcd\""
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_LISTEN_PEER_URLS=\"https://{}:2380\"",
-            current_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_LISTEN_CLIENT_URLS=\"https://{}:2379\"",
-            current_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf).expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf, "#[Clustering]")
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_INITIAL_ADVERTISE_PEER_URLS=\"https://{}:2380\"",
-            current_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_ADVERTISE_CLIENT_URLS=\"https://{}:2379\"",
-            current_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        let mut buffer = String::new();
-        for (ip, name) in &config.instance_hosts {
-            buffer.push_str(format!("etcd_{}=https://{}:2380,", name, ip).as_str());
-        }
-        buffer.pop();
-        writeln!(&mut etcd_conf, "ETCD_INITIAL_CLUSTER=\"{}\"", buffer)
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_INITIAL_CLUSTER_TOKEN=\"etcd-cluster\""
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf, "ETCD_INITIAL_CLUSTER_STATE=\"new\"")
-            .expect("Error happened when trying to write `etcd.conf`");
+    fn generate(current_ip: &String, current_name: &str, config: &Config) {
+        EtcdConfig::from(config, current_ip, current_name)
+            .write(&format!("to_send/{}/etcd.conf", current_ip));
     }
 }
 
@@ -199,14 +155,7 @@ Wants=network-online.target
 [Service]
 Type=notify
 EnvironmentFile=/opt/etcd/cfg/etcd.conf
-ExecStart=/opt/etcd/bin/etcd \
---cert-file=/opt/etcd/ssl/server.pem \
---key-file=/opt/etcd/ssl/server-key.pem \
---peer-cert-file=/opt/etcd/ssl/server.pem \
---peer-key-file=/opt/etcd/ssl/server-key.pem \
---trusted-ca-file=/opt/etcd/ssl/ca.pem \
---peer-trusted-ca-file=/opt/etcd/ssl/ca.pem \
---logger=zap
+ExecStart=/opt/etcd/bin/etcd
 Restart=on-failure
 LimitNOFILE=65536
 
@@ -224,8 +173,8 @@ pub fn start(config: &Config) {
     // Etcd does not distinguish masters or workers.
     tracing::info!("Preparing mutual .pem, .service and etcd binaries...");
     tracing::info!("Change working directory into `etcd`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/etcd");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("etcd");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `etcd`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -311,20 +260,30 @@ pub fn start(config: &Config) {
         check_dir_exist_or_create(path);
     }
 
-    tracing::info!("Untaring prepared etcd binary file");
-    Command::new("tar")
-        .arg("-zxf")
-        .arg("/rk8s/preparation/etcd-v3.4.9-linux-amd64.tar.gz")
-        .status()
-        .expect("Error happened when trying to untar `etcd` executable");
-
-    tracing::info!("Copying binaries to to_send/etcd/bin...");
-    Command::new("cp")
-        .arg("etcd-v3.4.9-linux-amd64/etcd")
-        .arg("etcd-v3.4.9-linux-amd64/etcdctl")
-        .arg("to_send/etcd/bin")
-        .status()
-        .expect("Error happened when trying to copy binaries to `to_send/etcd/bin`");
+    // Binaries are prepared once per architecture found among nodes.
+    let archs = arch::of_hosts(config);
+    let mut binaries = HashMap::new();
+    for arch in arch::distinct(&archs) {
+        let etcd = Artifact::etcd(config, arch);
+        let tarball = etcd.stage();
+
+        tracing::info!("Untaring prepared etcd {} binary file", arch);
+        check_dir_exist_or_create(PathBuf::from(arch.as_str()));
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(arch.as_str())
+            .status()
+            .expect("Error happened when trying to untar `etcd` executable");
+        binaries.insert(
+            arch,
+            [
+                format!("{}/{}/etcd", arch, etcd.dir),
+                format!("{}/{}/etcdctl", arch, etcd.dir),
+            ],
+        );
+    }
     tracing::info!("Binaries prepared");
 
     tracing::info!("Copying certificates to to_send/etcd/ssl...");
@@ -351,17 +310,25 @@ pub fn start(config: &Config) {
         Command::new("scp")
             .arg("-r")
             .arg("to_send/etcd")
-            .arg(format!("root@{}:/opt/", ip))
+            .arg(format!("root@{}:/opt/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("scp")
+            .args(&binaries[&archs[ip]])
+            .arg(format!("root@{}:/opt/etcd/bin", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
         Command::new("scp")
             .arg("to_send/etcd.service")
-            .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+            .arg(format!(
+                "root@{}:/usr/lib/systemd/system/",
+                networking::host(ip)
+            ))
             .status()
             .expect("Error happened when trying to send files to other nodes");
         Command::new("scp")
             .arg(format!("to_send/{}/etcd.conf", ip))
-            .arg(format!("root@{}:/opt/etcd/cfg", ip))
+            .arg(format!("root@{}:/opt/etcd/cfg", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
@@ -394,6 +361,10 @@ pub fn start(config: &Config) {
     for handle in handles {
         handle.join().unwrap();
     }
+    // Members only report healthy once enough of them are up for a quorum.
+    for (ip, name) in &config.instance_hosts {
+        health::wait(config, Component::Etcd, ip, name);
+    }
 
     env::set_current_dir(prev_dir).expect("Error happened when trying to change into `etcd`");
     tracing::info!(
@@ -1,9 +1,13 @@
+use crate::component_config::kube_apiserver;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -109,17 +113,21 @@ impl ServerCsr {
             CN: config.kube_apiserver_CN.to_owned(),
             hosts: {
                 let mut hosts = vec![
-                    "10.0.0.1".to_string(),
+                    networking::api_service_ip(config).to_string(),
                     "127.0.0.1".to_string(),
                     "kubernetes".to_string(),
                     "kubernetes.default".to_string(),
                     "kubernetes.default.svc".to_string(),
                     "kubernetes.default.svc.cluster".to_string(),
-                    "kubernetes.default.svc.cluster.local".to_string(),
+                    format!(
+                        "kubernetes.default.svc.{}",
+                        config.networking.cluster_domain
+                    ),
                 ];
                 for ip in config.instance_hosts.keys() {
                     hosts.push(ip.to_owned());
                 }
+                hosts.extend(networking::endpoint_hosts(config));
                 hosts
             },
             key: Key {
@@ -140,67 +148,11 @@ impl ServerCsr {
 struct KubeApiserverCfg;
 
 impl KubeApiserverCfg {
-    fn generate(current_ip: &String, config: &Config) {
-        let mut apiserver_conf = File::create(format!(
-            "to_send/{}/apiserver/kube-apiserver.conf",
-            current_ip
-        ))
-        .expect("Error happened when trying to create kube-apiserver configuration file");
-
-        writeln!(
-            &mut apiserver_conf,
-            r#"KUBE_APISERVER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \"#
-        )
-        .expect("Error happened when trying to write `kube-apiserver.conf`");
-        let mut buffer = String::new();
-        for ip in config.instance_hosts.keys() {
-            buffer.push_str(format!("https://{}:2379,", ip).as_str());
-        }
-        buffer.pop();
-        writeln!(&mut apiserver_conf, "--etcd-servers={}", buffer)
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(&mut apiserver_conf, "--bind-address={}", current_ip)
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(&mut apiserver_conf, "--secure-port=6443")
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(&mut apiserver_conf, "--advertise-address={}", current_ip)
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(
-            &mut apiserver_conf,
-r#"--allow-privileged=true \
---service-cluster-ip-range=10.0.0.0/24 \
---enable-admission-plugins=NamespaceLifecycle,LimitRanger,ServiceAccount,ResourceQuota,NodeRestriction \
---authorization-mode=RBAC,Node \
---enable-bootstrap-token-auth=true \
---token-auth-file=/opt/kubernetes/cfg/token.csv \
---service-node-port-range=30000-32767 \
---kubelet-client-certificate=/opt/kubernetes/ssl/server.pem \
---kubelet-client-key=/opt/kubernetes/ssl/server-key.pem \
---tls-cert-file=/opt/kubernetes/ssl/server.pem  \
---tls-private-key-file=/opt/kubernetes/ssl/server-key.pem \
---client-ca-file=/opt/kubernetes/ssl/ca.pem \
---service-account-key-file=/opt/kubernetes/ssl/ca-key.pem \
---service-account-issuer=api \
---service-account-signing-key-file=/opt/kubernetes/ssl/server-key.pem \
---etcd-cafile=/opt/etcd/ssl/ca.pem \
---etcd-certfile=/opt/etcd/ssl/server.pem \
---etcd-keyfile=/opt/etcd/ssl/server-key.pem \
---requestheader-client-ca-file=/opt/kubernetes/ssl/ca.pem \
---proxy-client-cert-file=/opt/kubernetes/ssl/server.pem \
---proxy-client-key-file=/opt/kubernetes/ssl/server-key.pem \
---requestheader-allowed-names=kubernetes \
---requestheader-extra-headers-prefix=X-Remote-Extra- \
---requestheader-group-headers=X-Remote-Group \
---requestheader-username-headers=X-Remote-User \
---enable-aggregator-routing=true \
---audit-log-maxage=30 \
---audit-log-maxbackup=3 \
---audit-log-maxsize=100 \
---audit-log-path=/opt/kubernetes/logs/k8s-audit.log""#,
-        )
-        .expect("Error happened when trying to write `kube-apiserver.conf`");
+    fn generate(current_ip: &String, config: &Config, version: &KubeVersion) {
+        kube_apiserver::flags(config, version, current_ip).write(
+            "KUBE_APISERVER_OPTS",
+            &format!("to_send/{}/apiserver/kube-apiserver.conf", current_ip),
+        );
     }
 }
 
@@ -231,8 +183,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -315,6 +267,7 @@ pub fn start(config: &Config) {
     KubeApiserverUnit::generate();
     tracing::info!("`kube-apiserver.service` generated");
 
+    let version = KubeVersion::from(config);
     for (ip, name) in &config.instance_hosts {
         // Apiserver config, ssl are only needed by masters.
         if name.contains("master") {
@@ -323,26 +276,29 @@ pub fn start(config: &Config) {
                 .arg("ca-key.pem")
                 .arg("server-key.pem")
                 .arg("server.pem")
-                .arg(format!("root@{}:/opt/kubernetes/ssl", ip))
+                .arg(format!("root@{}:/opt/kubernetes/ssl", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Certificates sent to master on {}", ip);
 
             tracing::info!("Generating `kube-apiserver.conf` to /opt/kubernetes/cfg...");
-            KubeApiserverCfg::generate(ip, config);
+            KubeApiserverCfg::generate(ip, config, &version);
             tracing::info!("`kube-apiserver.conf` generated");
 
             Command::new("scp")
                 .arg("to_send/token.csv")
                 .arg(format!("to_send/{}/apiserver/kube-apiserver.conf", ip))
-                .arg(format!("root@{}:/opt/kubernetes/cfg", ip))
+                .arg(format!("root@{}:/opt/kubernetes/cfg", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Configurations sent to master on {}", ip);
 
             Command::new("scp")
                 .arg("to_send/kube-apiserver.service")
-                .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+                .arg(format!(
+                    "root@{}:/usr/lib/systemd/system/",
+                    networking::host(ip)
+                ))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Systemd service sent to master on {}", ip);
@@ -365,10 +321,12 @@ pub fn start(config: &Config) {
                 .status()
                 .expect("Error happened when trying to enable apiserver");
             tracing::info!("kube-apiserver started on {}", ip);
+            health::wait(config, Component::Apiserver, ip, name);
         }
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
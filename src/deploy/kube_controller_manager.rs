@@ -1,38 +1,24 @@
+use crate::component_config::kube_controller_manager;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeControllerManagerCfg;
 
 impl KubeControllerManagerCfg {
-    fn generate() {
-        let mut controller_conf = File::create("to_send/kube-controller-manager.conf").expect(
-            "Error happened when trying to create kube-controller-manager configuration file",
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_controller_manager::flags(config, version).write(
+            "KUBE_CONTROLLER_MANAGER_OPTS",
+            "to_send/kube-controller-manager.conf",
         );
-
-        writeln!(
-            &mut controller_conf,
-            r#"KUBE_CONTROLLER_MANAGER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---leader-elect=true \
---kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig \
---bind-address=127.0.0.1 \
---allocate-node-cidrs=true \
---cluster-cidr=10.244.0.0/16 \
---service-cluster-ip-range=10.0.0.0/24 \
---cluster-signing-cert-file=/opt/kubernetes/ssl/ca.pem \
---cluster-signing-key-file=/opt/kubernetes/ssl/ca-key.pem  \
---root-ca-file=/opt/kubernetes/ssl/ca.pem \
---service-account-private-key-file=/opt/kubernetes/ssl/ca-key.pem \
---cluster-signing-duration=87600h0m0s"
-"#
-        )
-        .expect("Error happened when trying to write `kube-controller-manager.conf`");
     }
 }
 
@@ -108,8 +94,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("kube_controller_manager phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -146,7 +132,7 @@ pub fn start(config: &Config) {
     tracing::info!("Self-signed kube_controller_manager CA certificate generated");
 
     tracing::info!("Generating `kube-controller-manager.conf` to to_send/...");
-    KubeControllerManagerCfg::generate();
+    KubeControllerManagerCfg::generate(config, &KubeVersion::from(config));
     tracing::info!("`kube-controller-manager.conf` generated");
 
     tracing::info!("Generating `kube-controller-manager.service` to to_send/");
@@ -158,49 +144,43 @@ pub fn start(config: &Config) {
             Command::new("scp")
                 .arg("kube-controller-manager.pem")
                 .arg("kube-controller-manager-key.pem")
-                .arg(format!("root@{}:/opt/kubernetes/ssl", ip))
+                .arg(format!("root@{}:/opt/kubernetes/ssl", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Certificates sent to master on {}", ip);
 
             Command::new("scp")
                 .arg("to_send/kube-controller-manager.conf")
-                .arg(format!("root@{}:/opt/kubernetes/cfg", ip))
+                .arg(format!("root@{}:/opt/kubernetes/cfg", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Configurations sent to master on {}", ip);
 
             Command::new("scp")
                 .arg("to_send/kube-controller-manager.service")
-                .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+                .arg(format!(
+                    "root@{}:/usr/lib/systemd/system/",
+                    networking::host(ip)
+                ))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Systemd service sent to master on {}", ip);
 
-            // Generate kubeconfig on remote master.
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(format!("kubectl config set-cluster kubernetes --certificate-authority=/opt/kubernetes/ssl/ca.pem --embed-certs=true \
-                --server=https://{}:6443 --kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig", ip))
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-credentials kube-controller-manager --client-certificate=/opt/kubernetes/ssl/kube-controller-manager.pem \
-                --client-key=/opt/kubernetes/ssl/kube-controller-manager-key.pem --embed-certs=true --kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-context default --cluster=kubernetes --user=kube-controller-manager \
-                --kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config use-context default --kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
+            // Generate kubeconfig and send it to master.
+            Kubeconfig::new(
+                &networking::apiserver_url(config, ip),
+                "ca.pem",
+                "kube-controller-manager",
+                User::certificate(
+                    "kube-controller-manager.pem",
+                    "kube-controller-manager-key.pem",
+                ),
+            )
+            .upload(
+                ip,
+                "kube-controller-manager.kubeconfig",
+                "/opt/kubernetes/cfg/kube-controller-manager.kubeconfig",
+            );
 
             // Starting controller manager...
             tracing::info!("kube-controller-manager installed on {}, starting...", name);
@@ -220,10 +200,12 @@ pub fn start(config: &Config) {
                 .status()
                 .expect("Error happened when trying to enable controller-manager");
             tracing::info!("kube-controller-manager started on {}", ip);
+            health::wait(config, Component::ControllerManager, ip, name);
         }
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
@@ -1,60 +1,33 @@
+use crate::component_config::{self, kube_proxy, kube_proxy::KubeProxyConfiguration};
 use crate::config::Config;
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeProxyCfg;
 
 impl KubeProxyCfg {
-    fn generate() {
-        let mut kube_proxy_conf = File::create("to_send/kube-proxy.conf")
-            .expect("Error happened when trying to create kube-proxy configuration file");
-
-        writeln!(
-            &mut kube_proxy_conf,
-            r#"KUBE_PROXY_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---config=/opt/kubernetes/cfg/kube-proxy-config.yml"
-"#
-        )
-        .expect("Error happened when trying to write `kube-proxy.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_proxy::flags(config, version).write("KUBE_PROXY_OPTS", "to_send/kube-proxy.conf");
     }
 }
 
 struct KubeProxyConfig;
 
 impl KubeProxyConfig {
-    fn generate(current_ip: &String, current_name: &String) {
-        let mut kube_proxy_config = File::create(format!(
-            "to_send/{}/kube_proxy/kube-proxy-config.yml",
-            current_ip
-        ))
-        .expect("Error happened when trying to create kube-proxy configuration file");
-
-        writeln!(
-            &mut kube_proxy_config,
-            r#"kind: KubeProxyConfiguration
-apiVersion: kubeproxy.config.k8s.io/v1alpha1
-bindAddress: 0.0.0.0
-metricsBindAddress: 0.0.0.0:10249
-clientConnection:
-  kubeconfig: /opt/kubernetes/cfg/kube-proxy.kubeconfig"#
-        )
-        .expect("Error happened when trying to write `kube-proxy-config.yml`");
-        writeln!(
-            &mut kube_proxy_config,
-            "hostnameOverride: {} \\",
-            current_name
-        )
-        .expect("Error happened when trying to write `kube-proxy-config.yml`");
-        writeln!(
-            &mut kube_proxy_config,
-            r#"clusterCIDR: 10.244.0.0/16
-"#,
+    fn generate(current_ip: &String, current_name: &str, config: &Config, version: &KubeVersion) {
+        fs::write(
+            format!("to_send/{}/kube_proxy/kube-proxy-config.yml", current_ip),
+            component_config::to_yaml(
+                &KubeProxyConfiguration::from(config, version, current_name),
+                &config.components.kube_proxy.config_patch,
+            ),
         )
         .expect("Error happened when trying to write `kube-proxy-config.yml`");
     }
@@ -133,8 +106,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("kube_proxy phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -168,8 +141,9 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to execute `cfssljson`");
     tracing::info!("Self-signed kube_proxy CA certificate generated");
 
+    let version = KubeVersion::from(config);
     tracing::info!("Generating `kube-proxy.conf` to to_send/...");
-    KubeProxyCfg::generate();
+    KubeProxyCfg::generate(config, &version);
     tracing::info!("`kube-proxy.conf` generated");
 
     tracing::info!("Generating `kube-proxy.service` to to_send/...");
@@ -177,96 +151,75 @@ pub fn start(config: &Config) {
     tracing::info!("`kube-proxy.service` generated");
 
     for (ip, name) in &config.instance_hosts {
-        if name.contains("master") {
-            tracing::info!("Generating `kube-proxy-config.yml`...");
-            KubeProxyConfig::generate(ip, name);
-            tracing::info!("`kube-proxy-config.yml` generated");
-
-            Command::new("scp")
-                .arg("kube-proxy.pem")
-                .arg("kube-proxy-key.pem")
-                .arg(format!("root@{}:/opt/kubernetes/ssl", ip))
-                .status()
-                .expect("Error happened when trying to send files to other nodes");
-            tracing::info!("Certificates sent to master on {}", ip);
-
-            Command::new("scp")
-                .arg("to_send/kube-proxy.conf")
-                .arg(format!("to_send/{}/kube_proxy/kube-proxy-config.yml", ip))
-                .arg(format!("root@{}:/opt/kubernetes/cfg", ip))
-                .status()
-                .expect("Error happened when trying to send files to other nodes");
-            tracing::info!("Configurations sent to master on {}", ip);
-
-            Command::new("scp")
-                .arg("to_send/kube-proxy.service")
-                .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
-                .status()
-                .expect("Error happened when trying to send files to other nodes");
-            tracing::info!("Systemd service sent to master on {}", ip);
-
-            // Generate kubeconfig on remote master.
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(format!("kubectl config set-cluster kubernetes --certificate-authority=/opt/kubernetes/ssl/ca.pem --embed-certs=true \
-                --server=https://{}:6443 --kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig", ip))
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-credentials kube-proxy --client-certificate=/opt/kubernetes/ssl/kube-proxy.pem \
-                --client-key=/opt/kubernetes/ssl/kube-proxy-key.pem --embed-certs=true --kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(
-                    "kubectl config set-context default --cluster=kubernetes --user=kube-proxy \
-                --kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig",
-                )
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config use-context default --kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-
-            // Starting kubelet...
-            tracing::info!("kubelet installed on {}, starting...", name);
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("systemctl daemon-reload")
-                .status()
-                .expect("Error happened when trying to reload systemd daemons");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("systemctl start kube-proxy")
-                .status()
-                .expect("Error happened when trying to start kube-proxy");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("systemctl enable kube-proxy")
-                .status()
-                .expect("Error happened when trying to enable kube-proxy");
-            tracing::info!("kube-proxy started on {}", ip);
-
-            Command::new("scp")
-                .arg("/rk8s/preparation/calico.yaml")
-                .arg(format!("root@{}:/root", ip))
-                .status()
-                .expect("Error happened when trying to send files to other nodes");
-            tracing::info!("Systemd service sent to master on {}", ip);
-            tracing::info!("Deploying Calico...");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl apply -f /root/calico.yaml")
-                .status()
-                .expect("Error happened when trying to enable kubelet");
-        }
+        let role = if name.contains("master") {
+            "master"
+        } else {
+            "worker"
+        };
+        tracing::info!("Generating `kube-proxy-config.yml`...");
+        KubeProxyConfig::generate(ip, name, config, &version);
+        tracing::info!("`kube-proxy-config.yml` generated");
+
+        Command::new("scp")
+            .arg("kube-proxy.pem")
+            .arg("kube-proxy-key.pem")
+            .arg(format!("root@{}:/opt/kubernetes/ssl", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        tracing::info!("Certificates sent to {} on {}", role, ip);
+
+        Command::new("scp")
+            .arg("to_send/kube-proxy.conf")
+            .arg(format!("to_send/{}/kube_proxy/kube-proxy-config.yml", ip))
+            .arg(format!("root@{}:/opt/kubernetes/cfg", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        tracing::info!("Configurations sent to {} on {}", role, ip);
+
+        Command::new("scp")
+            .arg("to_send/kube-proxy.service")
+            .arg(format!(
+                "root@{}:/usr/lib/systemd/system/",
+                networking::host(ip)
+            ))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        tracing::info!("Systemd service sent to {} on {}", role, ip);
+
+        Kubeconfig::new(
+            &networking::node_apiserver_url(config, ip, name),
+            "ca.pem",
+            "kube-proxy",
+            User::certificate("kube-proxy.pem", "kube-proxy-key.pem"),
+        )
+        .upload(
+            ip,
+            "kube_proxy/kube-proxy.kubeconfig",
+            "/opt/kubernetes/cfg/kube-proxy.kubeconfig",
+        );
+
+        // Starting kube-proxy...
+        tracing::info!("kube-proxy installed on {}, starting...", name);
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl daemon-reload")
+            .status()
+            .expect("Error happened when trying to reload systemd daemons");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl start kube-proxy")
+            .status()
+            .expect("Error happened when trying to start kube-proxy");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl enable kube-proxy")
+            .status()
+            .expect("Error happened when trying to enable kube-proxy");
+        tracing::info!("kube-proxy started on {}", ip);
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
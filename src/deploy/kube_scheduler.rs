@@ -1,29 +1,22 @@
+use crate::component_config::kube_scheduler;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeSchedulerCfg;
 
 impl KubeSchedulerCfg {
-    fn generate() {
-        let mut scheduler_conf = File::create("to_send/kube-scheduler.conf")
-            .expect("Error happened when trying to create kube-scheduler configuration file");
-
-        writeln!(
-            &mut scheduler_conf,
-            r#"KUBE_SCHEDULER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---leader-elect \
---kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig \
---bind-address=127.0.0.1"
-"#
-        )
-        .expect("Error happened when trying to write `kube-scheduler.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_scheduler::flags(config, version)
+            .write("KUBE_SCHEDULER_OPTS", "to_send/kube-scheduler.conf");
     }
 }
 
@@ -99,8 +92,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -135,7 +128,7 @@ pub fn start(config: &Config) {
     tracing::info!("Self-signed kube_scheduler CA certificate generated");
 
     tracing::info!("Generating `kube-scheduler.conf` to to_send/...");
-    KubeSchedulerCfg::generate();
+    KubeSchedulerCfg::generate(config, &KubeVersion::from(config));
     tracing::info!("`kube-scheduler.conf` generated");
 
     tracing::info!("Generating `kube-scheduler.service` to /usr/lib/systemd/system/");
@@ -147,49 +140,40 @@ pub fn start(config: &Config) {
             Command::new("scp")
                 .arg("kube-scheduler.pem")
                 .arg("kube-scheduler-key.pem")
-                .arg(format!("root@{}:/opt/kubernetes/ssl", ip))
+                .arg(format!("root@{}:/opt/kubernetes/ssl", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Certificates sent to master on {}", ip);
 
             Command::new("scp")
                 .arg("to_send/kube-scheduler.conf")
-                .arg(format!("root@{}:/opt/kubernetes/cfg", ip))
+                .arg(format!("root@{}:/opt/kubernetes/cfg", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Configurations sent to master on {}", ip);
 
             Command::new("scp")
                 .arg("to_send/kube-scheduler.service")
-                .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+                .arg(format!(
+                    "root@{}:/usr/lib/systemd/system/",
+                    networking::host(ip)
+                ))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Systemd service sent to master on {}", ip);
 
-            // Generate kubeconfig on remote master.
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(format!("kubectl config set-cluster kubernetes --certificate-authority=/opt/kubernetes/ssl/ca.pem --embed-certs=true \
-                --server=https://{}:6443 --kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig", ip))
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-credentials kube-scheduler --client-certificate=/opt/kubernetes/ssl/kube-scheduler.pem \
-                --client-key=/opt/kubernetes/ssl/kube-scheduler-key.pem --embed-certs=true --kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-context default --cluster=kubernetes --user=kube-scheduler \
-                --kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config use-context default --kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
+            // Generate kubeconfig and send it to master.
+            Kubeconfig::new(
+                &networking::apiserver_url(config, ip),
+                "ca.pem",
+                "kube-scheduler",
+                User::certificate("kube-scheduler.pem", "kube-scheduler-key.pem"),
+            )
+            .upload(
+                ip,
+                "kube-scheduler.kubeconfig",
+                "/opt/kubernetes/cfg/kube-scheduler.kubeconfig",
+            );
 
             // Starting scheduler...
             tracing::info!("kube-scheduler installed on {}, starting...", name);
@@ -209,10 +193,12 @@ pub fn start(config: &Config) {
                 .status()
                 .expect("Error happened when trying to enable scheduler");
             tracing::info!("kube-scheduler started on {}", ip);
+            health::wait(config, Component::Scheduler, ip, name);
         }
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
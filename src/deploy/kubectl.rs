@@ -1,9 +1,11 @@
 use crate::config::Config;
+use crate::kubeconfig::{self, Kubeconfig, User};
+use crate::networking;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[allow(non_snake_case)]
@@ -54,8 +56,8 @@ impl KubectlCsr {
 pub fn start(config: &Config) {
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -94,7 +96,7 @@ pub fn start(config: &Config) {
             Command::new("scp")
                 .arg("admin.pem")
                 .arg("admin-key.pem")
-                .arg(format!("root@{}:/opt/kubernetes/ssl", ip))
+                .arg(format!("root@{}:/opt/kubernetes/ssl", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             tracing::info!("Certificates sent to master on {}", ip);
@@ -106,32 +108,14 @@ pub fn start(config: &Config) {
                 .status()
                 .expect("Error happened when trying to create directory");
 
-            // Generate kubeconfig on remote master.
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(format!("kubectl config set-cluster kubernetes --certificate-authority=/opt/kubernetes/ssl/ca.pem --embed-certs=true \
-                --server=https://{}:6443 --kubeconfig=/root/.kube/config", ip))
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config set-credentials cluster-admin --client-certificate=/opt/kubernetes/ssl/admin.pem \
-                --client-key=/opt/kubernetes/ssl/admin-key.pem --embed-certs=true --kubeconfig=/root/.kube/config")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg(
-                    "kubectl config set-context default --cluster=kubernetes --user=cluster-admin \
-                --kubeconfig=/root/.kube/config",
-                )
-                .status()
-                .expect("Error happened when trying to execute kubectl");
-            Command::new("ssh")
-                .arg(format!("root@{}", ip))
-                .arg("kubectl config use-context default --kubeconfig=/root/.kube/config")
-                .status()
-                .expect("Error happened when trying to execute kubectl");
+            // Generate kubeconfig and send it to master.
+            Kubeconfig::new(
+                &networking::apiserver_url(config, ip),
+                "ca.pem",
+                "cluster-admin",
+                User::certificate("admin.pem", "admin-key.pem"),
+            )
+            .upload(ip, "admin.kubeconfig", "/root/.kube/config");
 
             Command::new("ssh")
                 .arg(format!("root@{}", ip))
@@ -143,7 +127,15 @@ pub fn start(config: &Config) {
         }
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    // Lets `rk8s kubeconfig export` and kubectl work from this machine.
+    kubeconfig::admin(config).write(kubeconfig::admin_path());
+    tracing::info!(
+        "Admin kubeconfig saved to {}, see `rk8s kubeconfig export`",
+        kubeconfig::admin_path().display()
+    );
+
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
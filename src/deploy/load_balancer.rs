@@ -0,0 +1,133 @@
+use std::env;
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::load_balancer;
+use crate::networking;
+use crate::workdir;
+
+pub fn start(config: &Config) {
+    let Some(lb) = &config.load_balancer else {
+        return;
+    };
+    tracing::info!("load_balancer phase started");
+    tracing::info!("Change working directory into `k8s`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    tracing::info!("Generating `haproxy.cfg` and `check_apiserver.sh` to to_send/...");
+    fs::write(
+        "to_send/haproxy.cfg",
+        load_balancer::haproxy_cfg(config, &load_balancer::vip_bind(lb)),
+    )
+    .expect("Error happened when trying to write `haproxy.cfg`");
+    fs::write(
+        "to_send/check_apiserver.sh",
+        load_balancer::check_script(lb),
+    )
+    .expect("Error happened when trying to write `check_apiserver.sh`");
+    fs::set_permissions("to_send/check_apiserver.sh", Permissions::from_mode(0o755))
+        .expect("Error happened when trying to make `check_apiserver.sh` executable");
+    tracing::info!("`haproxy.cfg` and `check_apiserver.sh` generated");
+
+    for (ip, name) in load_balancer::masters(config) {
+        tracing::info!("Installing HAProxy and keepalived on {}...", name);
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(load_balancer::install_command(&["haproxy", "keepalived"]))
+            .status()
+            .expect("Error happened when trying to install HAProxy and keepalived");
+        if !status.success() {
+            panic!(
+                "Failed to install HAProxy and keepalived on {} ({}), \
+                please install them with the package manager of the node",
+                name, ip
+            );
+        }
+
+        let interface = match &lb.interface {
+            Some(interface) => interface.to_owned(),
+            None => {
+                let output = Command::new("ssh")
+                    .arg(format!("root@{}", ip))
+                    .arg(load_balancer::route_command(lb))
+                    .output()
+                    .expect("Error happened when trying to look up the route to the VIP");
+                let route = String::from_utf8_lossy(&output.stdout);
+                load_balancer::route_interface(&route)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "No interface of {} ({}) routes to {}, please set `load_balancer.interface`",
+                            name, ip, lb.vip
+                        )
+                    })
+                    .to_owned()
+            }
+        };
+        tracing::info!("VIP {} goes on {} of {}", lb.vip, interface, name);
+
+        fs::create_dir_all(format!("to_send/{}/load_balancer", ip))
+            .expect("Error happened when trying to create load balancer directory");
+        fs::write(
+            format!("to_send/{}/load_balancer/keepalived.conf", ip),
+            load_balancer::keepalived_conf(config, lb, ip, &interface),
+        )
+        .expect("Error happened when trying to write `keepalived.conf`");
+
+        Command::new("scp")
+            .arg("to_send/haproxy.cfg")
+            .arg(format!("root@{}:/etc/haproxy/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("scp")
+            .arg("-p")
+            .arg("to_send/check_apiserver.sh")
+            .arg(format!("to_send/{}/load_balancer/keepalived.conf", ip))
+            .arg(format!("root@{}:/etc/keepalived/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        tracing::info!("Configurations sent to master on {}", ip);
+
+        tracing::info!("Starting HAProxy and keepalived on {}...", name);
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl enable haproxy keepalived && systemctl restart haproxy keepalived")
+            .status()
+            .expect("Error happened when trying to start HAProxy and keepalived");
+        if !status.success() {
+            panic!(
+                "HAProxy or keepalived failed to start on {}, please check \
+                `journalctl -u haproxy -u keepalived` there",
+                name
+            );
+        }
+        tracing::info!("HAProxy and keepalived started on {}", ip);
+    }
+
+    let (ip, name) = config.first_master();
+    tracing::info!("Waiting for the apiserver behind {}...", lb.vip);
+    let status = Command::new("ssh")
+        .arg(format!("root@{}", ip))
+        .arg(load_balancer::wait_command(config))
+        .status()
+        .expect("Error happened when trying to reach the control plane endpoint");
+    if !status.success() {
+        panic!(
+            "The apiserver can not be reached through the control plane endpoint from {}, \
+            please check `ip addr` and `journalctl -u keepalived` on masters",
+            name
+        );
+    }
+    tracing::info!("Control plane endpoint is ready");
+
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
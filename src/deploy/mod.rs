@@ -1,3 +1,8 @@
+pub mod addons;
+pub mod cni;
+pub mod containerd;
+pub mod coredns;
+pub mod crio;
 pub mod docker;
 pub mod etcd;
 pub mod kube_apiserver;
@@ -6,5 +11,8 @@ pub mod kube_proxy;
 pub mod kube_scheduler;
 pub mod kubectl;
 pub mod kubelet;
+pub mod load_balancer;
+pub mod node_local_load_balancer;
+pub mod oci_runtime;
 pub mod pre_check;
 pub mod prepare_kube;
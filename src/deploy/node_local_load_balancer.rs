@@ -0,0 +1,81 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::load_balancer;
+use crate::networking;
+use crate::workdir;
+
+pub fn start(config: &Config) {
+    if !config.node_local_load_balancer {
+        return;
+    }
+    tracing::info!("node_local_load_balancer phase started");
+    tracing::info!("Change working directory into `k8s`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    tracing::info!("Generating `haproxy.cfg` to to_send/node_local/...");
+    fs::create_dir_all("to_send/node_local")
+        .expect("Error happened when trying to create `to_send/node_local`");
+    fs::write(
+        "to_send/node_local/haproxy.cfg",
+        load_balancer::haproxy_cfg(config, &load_balancer::node_local_bind(config)),
+    )
+    .expect("Error happened when trying to write `haproxy.cfg`");
+    tracing::info!("`haproxy.cfg` generated");
+
+    for (ip, name) in &config.instance_hosts {
+        // Masters reach their own apiserver.
+        if name.contains("master") {
+            continue;
+        }
+        tracing::info!("Installing HAProxy on {}...", name);
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(load_balancer::install_command(&["haproxy"]))
+            .status()
+            .expect("Error happened when trying to install HAProxy");
+        if !status.success() {
+            panic!(
+                "Failed to install HAProxy on {} ({}), \
+                please install it with the package manager of the node",
+                name, ip
+            );
+        }
+
+        Command::new("scp")
+            .arg("to_send/node_local/haproxy.cfg")
+            .arg(format!("root@{}:/etc/haproxy/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        tracing::info!("Configurations sent to worker on {}", ip);
+
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("systemctl enable haproxy && systemctl restart haproxy")
+            .status()
+            .expect("Error happened when trying to start HAProxy");
+        if !status.success() {
+            panic!(
+                "HAProxy failed to start on {}, please check `journalctl -u haproxy` there",
+                name
+            );
+        }
+        tracing::info!(
+            "HAProxy listening on {} of {}",
+            load_balancer::node_local_bind(config),
+            name
+        );
+    }
+
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::arch;
+use crate::config::Config;
+use crate::networking;
+use crate::oci::{self, OciRuntime, SMOKE_TEST_IMAGE};
+use crate::runtime::Runtime;
+use crate::workdir;
+
+pub fn start(config: &Config) {
+    // Install youki or crun on the nodes picking them, before the container
+    // runtime referring to them is started.
+    tracing::info!("Preparing OCI runtime binaries...");
+    tracing::info!("Change working directory into `oci`");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::staging("oci");
+    check_dir_exist_or_create(work_dir.to_path_buf());
+    env::set_current_dir(work_dir).expect("Error happened when trying to change into `oci`");
+    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
+
+    // Binaries are prepared once per runtime and architecture.
+    let archs = arch::of_hosts(config);
+    let runtimes = oci::of_hosts(config);
+    let mut binaries = HashMap::new();
+    for (ip, runtime) in &runtimes {
+        let arch = archs[ip];
+        if binaries.contains_key(&(*runtime, arch)) {
+            continue;
+        }
+        let Some(artifact) = runtime.artifact(config, arch) else {
+            continue;
+        };
+        let staged = artifact.stage();
+        let binary = if artifact.dir.is_empty() {
+            staged
+        } else {
+            tracing::info!("untaring {} {} binary...", runtime, arch);
+            let dir = PathBuf::from(arch.as_str()).join(&artifact.dir);
+            check_dir_exist_or_create(dir.clone());
+            Command::new("tar")
+                .arg("-zxf")
+                .arg(staged)
+                .arg("--directory")
+                .arg(&dir)
+                .status()
+                .unwrap_or_else(|_| panic!("Error happened when trying to untar `{}`", runtime));
+            dir.join(runtime.as_str())
+        };
+        binaries.insert((*runtime, arch), binary);
+    }
+
+    for (ip, name) in &config.instance_hosts {
+        let runtime = runtimes[ip];
+        let Some(path) = runtime.path() else {
+            tracing::info!(
+                "{} on {} uses runc shipped by its container runtime",
+                name,
+                ip
+            );
+            continue;
+        };
+        tracing::info!("Installing {} on {}...", runtime, name);
+        Command::new("scp")
+            .arg(&binaries[&(runtime, archs[ip])])
+            .arg(format!("root@{}:{}", networking::host(ip), path))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(format!("chmod +x {}", path))
+            .status()
+            .expect("Error happened when trying to install OCI runtime on other nodes");
+        tracing::info!("{} installed on {}", runtime, ip);
+    }
+
+    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `rk8s`");
+    tracing::info!(
+        "Change working directory back to {}",
+        env::current_dir().unwrap().display()
+    );
+}
+
+/// Run a trivial container on every node with its default OCI runtime,
+/// catching a broken runtime before kubelet starts scheduling pods on it.
+pub fn smoke_test(config: &Config) {
+    let container_runtime = Runtime::from(config);
+    for (ip, name) in &config.instance_hosts {
+        let runtime = OciRuntime::of(config, ip);
+        tracing::info!("Running a test container on {} with {}...", name, runtime);
+        let command = match container_runtime {
+            Runtime::Docker => format!(
+                "docker run --rm --runtime={} {} true",
+                runtime, SMOKE_TEST_IMAGE
+            ),
+            Runtime::Containerd => format!(
+                "(ctr -n k8s.io images ls -q | grep -qx {0} || ctr -n k8s.io images pull --hosts-dir=/etc/containerd/certs.d {0}) \
+                && ctr -n k8s.io run --rm --runc-binary={1} {0} rk8s-smoke-test true",
+                SMOKE_TEST_IMAGE,
+                runtime.path().unwrap_or("runc")
+            ),
+            // CRI-O has no client of its own, a host network sandbox is
+            // enough to get the OCI runtime spawning the pause container.
+            Runtime::CriO => format!(
+                r#"echo '{{"metadata":{{"name":"rk8s-smoke-test","namespace":"default","uid":"rk8s-smoke-test"}},"linux":{{"security_context":{{"namespace_options":{{"network":2}}}}}}}}' > /tmp/rk8s-smoke-test.json \
+                && id=$(crictl --runtime-endpoint={} runp --runtime={} /tmp/rk8s-smoke-test.json) \
+                && crictl stopp $id && crictl rmp $id; status=$?; rm -f /tmp/rk8s-smoke-test.json; exit $status"#,
+                container_runtime.endpoint().unwrap(),
+                runtime
+            ),
+        };
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(command)
+            .status()
+            .expect("Error happened when trying to run a test container on other nodes");
+        if !status.success() {
+            panic!(
+                "Test container failed to run on {} ({}) with {}, \
+                please check `journalctl -u {}` there",
+                name,
+                ip,
+                runtime,
+                container_runtime.unit()
+            );
+        }
+        tracing::info!("{} works on {}", runtime, name);
+    }
+}
+
+fn check_dir_exist_or_create(path: PathBuf) {
+    if !path.is_dir() {
+        fs::create_dir_all(path).expect("Error happened when trying to create path");
+    }
+}
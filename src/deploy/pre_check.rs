@@ -1,22 +1,30 @@
 use crate::config::Config;
+use crate::networking;
+use crate::proxy::{self, ProxyMode};
+use crate::workdir;
 use std::fs::{self, File};
 use std::io::Write;
 use std::process::Command;
 
 pub fn start(config: &Config) {
     tracing::info!("Pre check started");
-    fs::create_dir("pre_check").unwrap();
+    // Both are named `k8s.conf` on nodes, under different directories.
+    let staging = workdir::staging("pre_check");
+    let sysctl_conf = staging.join("sysctl/k8s.conf");
+    let modules_conf = staging.join("modules/k8s.conf");
+    for dir in ["sysctl", "modules"] {
+        fs::create_dir_all(staging.join(dir))
+            .expect("Error happened when trying to create `pre_check` directory");
+    }
     let mut k8s_conf =
-        File::create("k8s.conf").expect("Error happened when trying to create `k8s.conf` file");
+        File::create(&sysctl_conf).expect("Error happened when trying to create `k8s.conf` file");
     k8s_conf
-        .write_all(
-            b"net.bridge.bridge-nf-call-ip6tables = 1\nnet.bridge.bridge-nf-call-iptables = 1\n",
-        )
+        .write_all(networking::sysctl_conf(config).as_bytes())
         .expect("Error happened when trying to write to `/etc/sysctl.d/k8s.conf`");
-    let mut k8s_module = File::create("pre_check/k8s.conf")
-        .expect("Error happened when trying to create `k8s.conf` file");
+    let mut k8s_module =
+        File::create(&modules_conf).expect("Error happened when trying to create `k8s.conf` file");
     k8s_module
-        .write_all(b"br_netfilter\n")
+        .write_all(proxy::modules_load_conf(config).as_bytes())
         .expect("Error happened when trying to write to `/etc/sysctl.d/k8s.conf`");
 
     for (ip, name) in &config.instance_hosts {
@@ -81,16 +89,33 @@ pub fn start(config: &Config) {
 
         tracing::info!("Setting `/etc/sysctl.d/k8s.conf` according to configuration...");
         Command::new("scp")
-            .arg("k8s.conf")
-            .arg(format!("root@{}:/etc/sysctl.d", ip))
+            .arg(&sysctl_conf)
+            .arg(format!("root@{}:/etc/sysctl.d", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
         Command::new("scp")
-            .arg("pre_check/k8s.conf")
-            .arg(format!("root@{}:/etc/modules-load.d", ip))
+            .arg(&modules_conf)
+            .arg(format!("root@{}:/etc/modules-load.d", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
+        // Modules have to be there before the bridge knobs can be set.
+        tracing::info!("Loading kernel modules...");
+        let status = Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(proxy::preflight_command(config))
+            .status()
+            .expect("Error happened when trying to load kernel modules");
+        if !status.success() {
+            panic!(
+                "{} ({}) is missing what kube-proxy needs in `{}` mode",
+                name,
+                ip,
+                ProxyMode::from(config)
+            );
+        }
+        tracing::info!("Kernel modules loaded");
+
         Command::new("ssh")
             .arg(format!("root@{}", ip))
             .arg("sysctl --system")
@@ -1,14 +1,19 @@
+use crate::arch;
+use crate::artifact::Artifact;
 use crate::config::Config;
+use crate::networking;
+use crate::workdir;
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::process::Command;
 
 pub fn start(config: &Config) {
     tracing::info!("Start preparing kubernetes binaries...");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -22,26 +27,60 @@ pub fn start(config: &Config) {
     let logs_path = PathBuf::from("to_send/kubernetes/logs");
     check_dir_exist_or_create(logs_path);
 
-    tracing::info!("Untaring prepared kubernetes binary file");
-    Command::new("tar")
-        .arg("-zxf")
-        .arg("/rk8s/preparation/kubernetes-server-linux-amd64.tar.gz")
-        .status()
-        .expect("Error happened when trying to untar `kubernetes` executable");
-
-    tracing::info!("Copying binaries to to_send/");
-    Command::new("cp")
-        // Needed by master.
-        .arg("kubernetes/server/bin/kube-apiserver")
-        .arg("kubernetes/server/bin/kube-controller-manager")
-        .arg("kubernetes/server/bin/kube-scheduler")
-        .arg("kubernetes/server/bin/kubectl")
-        // Needed by worker.
-        .arg("kubernetes/server/bin/kubelet")
-        .arg("kubernetes/server/bin/kube-proxy")
-        .arg("to_send/")
-        .status()
-        .expect("Error happened when trying to copy binaries to `to_send/etcd/bin`");
+    // Binaries are prepared once per architecture found among nodes.
+    let archs = arch::of_hosts(config);
+    let mut cni_plugins = HashMap::new();
+    for arch in arch::distinct(&archs) {
+        let kubernetes = Artifact::kubernetes(config, arch);
+        let tarball = kubernetes.stage();
+
+        tracing::info!("Untaring prepared kubernetes {} binary file", arch);
+        check_dir_exist_or_create(PathBuf::from(arch.as_str()));
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(arch.as_str())
+            .status()
+            .expect("Error happened when trying to untar `kubernetes` executable");
+
+        let cni = Artifact::cni(config, arch);
+        let tarball = cni.stage();
+
+        tracing::info!("Untaring prepared CNI {} plugins", arch);
+        let cni_path = PathBuf::from(format!("to_send/{}/{}", arch, cni.dir));
+        check_dir_exist_or_create(cni_path.clone());
+        Command::new("tar")
+            .arg("-zxf")
+            .arg(tarball)
+            .arg("--directory")
+            .arg(&cni_path)
+            .status()
+            .expect("Error happened when trying to untar CNI plugins");
+        let plugins: Vec<PathBuf> = fs::read_dir(&cni_path)
+            .expect("Error happened when trying to read untared CNI plugins")
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        cni_plugins.insert(arch, plugins);
+
+        tracing::info!("Copying binaries to to_send/{}/", arch);
+        Command::new("cp")
+            // Needed by master.
+            .arg(format!("{}/{}/kube-apiserver", arch, kubernetes.dir))
+            .arg(format!(
+                "{}/{}/kube-controller-manager",
+                arch, kubernetes.dir
+            ))
+            .arg(format!("{}/{}/kube-scheduler", arch, kubernetes.dir))
+            .arg(format!("{}/{}/kubectl", arch, kubernetes.dir))
+            // Needed by worker.
+            .arg(format!("{}/{}/kubelet", arch, kubernetes.dir))
+            .arg(format!("{}/{}/kube-proxy", arch, kubernetes.dir))
+            .arg(format!("to_send/{}/", arch))
+            .status()
+            .expect("Error happened when trying to copy binaries to `to_send/`");
+    }
     tracing::info!("Binaries prepared");
 
     for (ip, name) in &config.instance_hosts {
@@ -53,13 +92,24 @@ pub fn start(config: &Config) {
         Command::new("scp")
             .arg("-r")
             .arg("to_send/kubernetes")
-            .arg(format!("root@{}:/opt/", ip))
+            .arg(format!("root@{}:/opt/", networking::host(ip)))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        let arch = archs[ip];
+        Command::new("scp")
+            .arg(format!("to_send/{}/kubelet", arch))
+            .arg(format!("to_send/{}/kube-proxy", arch))
+            .arg(format!("root@{}:/opt/kubernetes/bin", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
+        Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg("mkdir -p /opt/cni/bin")
+            .status()
+            .expect("Error happened when trying to create CNI directory on other nodes");
         Command::new("scp")
-            .arg("to_send/kubelet")
-            .arg("to_send/kube-proxy")
-            .arg(format!("root@{}:/opt/kubernetes/bin", ip))
+            .args(&cni_plugins[&arch])
+            .arg(format!("root@{}:/opt/cni/bin", networking::host(ip)))
             .status()
             .expect("Error happened when trying to send files to other nodes");
 
@@ -79,15 +129,15 @@ pub fn start(config: &Config) {
 
             tracing::info!("Found master: {} on {}, sending kubernetes apiserver, controller-manager, scheduler, kubectl...", name, ip);
             Command::new("scp")
-                .arg("to_send/kube-apiserver")
-                .arg("to_send/kube-controller-manager")
-                .arg("to_send/kube-scheduler")
-                .arg(format!("root@{}:/opt/kubernetes/bin", ip))
+                .arg(format!("to_send/{}/kube-apiserver", arch))
+                .arg(format!("to_send/{}/kube-controller-manager", arch))
+                .arg(format!("to_send/{}/kube-scheduler", arch))
+                .arg(format!("root@{}:/opt/kubernetes/bin", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
             Command::new("scp")
-                .arg("to_send/kubectl")
-                .arg(format!("root@{}:/usr/bin", ip))
+                .arg(format!("to_send/{}/kubectl", arch))
+                .arg(format!("root@{}:/usr/bin", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other nodes");
         }
@@ -99,7 +149,8 @@ pub fn start(config: &Config) {
         check_dir_exist_or_create(kube_proxy_path);
     }
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
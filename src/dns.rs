@@ -0,0 +1,34 @@
+use crate::config::Config;
+use crate::oci::SMOKE_TEST_IMAGE;
+use crate::template;
+
+pub fn manifest(config: &Config) -> String {
+    let dns = &config.cluster_dns;
+    let upstream_resolvers = if dns.upstream_resolvers.is_empty() {
+        "/etc/resolv.conf".to_owned()
+    } else {
+        dns.upstream_resolvers.join(" ")
+    };
+    template::render(
+        include_str!("../templates/addons/coredns.yaml"),
+        &[
+            ("cluster_domain", &config.networking.cluster_domain),
+            ("dns_service_ip", &config.networking.dns_service_ip),
+            ("upstream_resolvers", &upstream_resolvers),
+        ],
+    )
+}
+
+/// Shell command waiting for CoreDNS pods to become ready.
+pub const WAIT_COMMAND: &str =
+    "kubectl -n kube-system rollout status deployment/coredns --timeout=300s";
+
+/// Shell command resolving `kubernetes.default` from a throwaway pod, through
+/// the service IP kubelet hands to pods.
+pub fn test_command(config: &Config) -> String {
+    format!(
+        "kubectl run rk8s-dns-test --image={} --restart=Never --rm -i --quiet \
+        --pod-running-timeout=300s -- nslookup kubernetes.default.svc.{} {}",
+        SMOKE_TEST_IMAGE, config.networking.cluster_domain, config.networking.dns_service_ip
+    )
+}
@@ -0,0 +1,142 @@
+use crate::config::Config;
+use crate::networking;
+use std::fmt;
+use std::process::{Command, Output};
+
+// Lines of `journalctl` shown when a component does not become healthy.
+const JOURNAL_LINES: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    Etcd,
+    Apiserver,
+    ControllerManager,
+    Scheduler,
+    Kubelet,
+}
+
+impl Component {
+    /// Systemd unit the component runs as, also the key of its timeout.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Component::Etcd => "etcd",
+            Component::Apiserver => "kube-apiserver",
+            Component::ControllerManager => "kube-controller-manager",
+            Component::Scheduler => "kube-scheduler",
+            Component::Kubelet => "kubelet",
+        }
+    }
+
+    /// Shell command succeeding once the component on `ip` reports healthy.
+    pub fn probe(&self, config: &Config, ip: &str) -> String {
+        // `-g` keeps curl from globbing the brackets of IPv6 hosts.
+        let curl = "curl -gsf --max-time 3 -o /dev/null";
+        match self {
+            Component::Etcd => format!(
+                "{} --cacert /opt/etcd/ssl/ca.pem --cert /opt/etcd/ssl/server.pem \
+                --key /opt/etcd/ssl/server-key.pem https://{}:2379/health",
+                curl,
+                networking::host(ip)
+            ),
+            Component::Apiserver => format!(
+                "{} --cacert /opt/kubernetes/ssl/ca.pem --cert /opt/kubernetes/ssl/server.pem \
+                --key /opt/kubernetes/ssl/server-key.pem https://{}:{}/readyz",
+                curl,
+                networking::host(ip),
+                config.networking.apiserver_port
+            ),
+            // Both serve with a certificate they sign themselves.
+            Component::ControllerManager => format!("{} -k https://127.0.0.1:10257/healthz", curl),
+            Component::Scheduler => format!("{} -k https://127.0.0.1:10259/healthz", curl),
+            Component::Kubelet => format!("{} http://127.0.0.1:10248/healthz", curl),
+        }
+    }
+
+    /// Seconds the component gets to become healthy.
+    pub fn timeout(&self, config: &Config) -> u64 {
+        let readiness = &config.readiness;
+        readiness
+            .timeouts
+            .get(self.unit())
+            .copied()
+            .unwrap_or(readiness.timeout)
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.unit())
+    }
+}
+
+/// Run `command` on the node at `ip`, or here when there is none.
+fn run(ip: Option<&str>, command: &str) -> Output {
+    match ip {
+        Some(ip) => Command::new("ssh")
+            .arg(format!("root@{}", ip))
+            .arg(command)
+            .output(),
+        None => Command::new("sh").arg("-c").arg(command).output(),
+    }
+    .expect("Error happened when trying to check component health")
+}
+
+/// Last lines the unit logged on the node at `ip`, or here.
+pub fn journal(ip: Option<&str>, unit: &str) -> String {
+    let output = run(
+        ip,
+        &format!("journalctl -u {} -n {} --no-pager", unit, JOURNAL_LINES),
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Shell command retrying `probe` every two seconds for `timeout` seconds.
+fn poll_command(probe: &str, timeout: u64) -> String {
+    format!(
+        "deadline=$(($(date +%s) + {})); until {}; do \
+        [ $(date +%s) -ge $deadline ] && exit 1; sleep 2; done",
+        timeout, probe
+    )
+}
+
+fn wait_on(config: &Config, component: Component, ip: Option<&str>, probe_ip: &str, name: &str) {
+    let timeout = component.timeout(config);
+    tracing::info!(
+        "Waiting up to {}s for {} on {} to become healthy...",
+        timeout,
+        component,
+        name
+    );
+    let output = run(
+        ip,
+        &poll_command(&component.probe(config, probe_ip), timeout),
+    );
+    if !output.status.success() {
+        panic!(
+            "{} on {} ({}) did not become healthy within {}s, its last logs are:\n{}",
+            component,
+            name,
+            probe_ip,
+            timeout,
+            journal(ip, component.unit())
+        );
+    }
+    tracing::info!("{} is healthy on {}", component, name);
+}
+
+/// Wait for `component` on the node at `ip` to report healthy, failing with
+/// its recent logs.
+pub fn wait(config: &Config, component: Component, ip: &str, name: &str) {
+    wait_on(config, component, Some(ip), ip, name);
+}
+
+/// Same as `wait`, for the machine rk8s runs on.
+pub fn wait_local(config: &Config, component: Component) {
+    wait_on(
+        config,
+        component,
+        None,
+        &config.instance_ip,
+        &config.instance_name,
+    );
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::process::Command;
+
+use crate::cni::Cni;
+use crate::config::Config;
+
+pub fn start(config: &Config) {
+    let cni = Cni::from(config);
+    tracing::info!("Deploying {} network plugin...", cni);
+    tracing::info!("Generating `cni.yaml` to /opt/kubernetes/cfg...");
+    fs::write("/opt/kubernetes/cfg/cni.yaml", cni.manifest(config))
+        .expect("Error happened when trying to write `cni.yaml`");
+    tracing::info!("`cni.yaml` generated");
+
+    let status = Command::new("kubectl")
+        .arg("apply")
+        .arg("-f")
+        .arg("/opt/kubernetes/cfg/cni.yaml")
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!("Failed to apply {} manifest", cni);
+    }
+
+    tracing::info!("Waiting for {} pods to become ready...", cni);
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(cni.wait_command())
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        let (namespace, _) = cni.daemonset();
+        panic!(
+            "{} pods did not become ready, please check `kubectl -n {} get pods`",
+            cni, namespace
+        );
+    }
+    tracing::info!("{} is ready", cni);
+}
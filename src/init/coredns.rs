@@ -0,0 +1,48 @@
+use std::fs;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::dns;
+
+pub fn start(config: &Config) {
+    tracing::info!("Deploying CoreDNS...");
+    tracing::info!("Generating `coredns.yaml` to /opt/kubernetes/cfg...");
+    fs::write("/opt/kubernetes/cfg/coredns.yaml", dns::manifest(config))
+        .expect("Error happened when trying to write `coredns.yaml`");
+    tracing::info!("`coredns.yaml` generated");
+
+    let status = Command::new("kubectl")
+        .arg("apply")
+        .arg("-f")
+        .arg("/opt/kubernetes/cfg/coredns.yaml")
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!("Failed to apply CoreDNS manifest");
+    }
+
+    tracing::info!("Waiting for CoreDNS pods to become ready...");
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(dns::WAIT_COMMAND)
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!("CoreDNS pods did not become ready, please check `kubectl -n kube-system get pods`");
+    }
+
+    tracing::info!("Resolving `kubernetes.default` from a test pod...");
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(dns::test_command(config))
+        .status()
+        .expect("Error happened when trying to execute kubectl");
+    if !status.success() {
+        panic!(
+            "`kubernetes.default` could not be resolved through {}, \
+            please check `kubectl -n kube-system logs deployment/coredns`",
+            config.networking.dns_service_ip
+        );
+    }
+    tracing::info!("CoreDNS is ready");
+}
@@ -1,9 +1,12 @@
+use crate::component_config::etcd::EtcdConfig;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::networking;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -127,63 +130,8 @@ struct ETCDCfg;
 
 impl ETCDCfg {
     fn generate(config: &Config) {
-        let mut etcd_conf = File::create("/opt/etcd/cfg/etcd.conf")
-            .expect("Error happened when trying to create etcd configuration file");
-
-        writeln!(&mut etcd_conf, "#[Member]")
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_NAME=\"etcd_{}\"",
-            config.instance_name
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_DATA_DIR=\"/var/lib/etcd/default.etcd\""
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_LISTEN_PEER_URLS=\"https://{}:2380\"",
-            config.instance_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_LISTEN_CLIENT_URLS=\"https://{}:2379\"",
-            config.instance_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf).expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf, "#[Clustering]")
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_INITIAL_ADVERTISE_PEER_URLS=\"https://{}:2380\"",
-            config.instance_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_ADVERTISE_CLIENT_URLS=\"https://{}:2379\"",
-            config.instance_ip
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        let mut buffer = String::new();
-        for (ip, name) in &config.instance_hosts {
-            buffer.push_str(format!("etcd_{}=https://{}:2380,", name, ip).as_str());
-        }
-        buffer.pop();
-        writeln!(&mut etcd_conf, "ETCD_INITIAL_CLUSTER=\"{}\"", buffer)
-            .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(
-            &mut etcd_conf,
-            "ETCD_INITIAL_CLUSTER_TOKEN=\"etcd-cluster\""
-        )
-        .expect("Error happened when trying to write `etcd.conf`");
-        writeln!(&mut etcd_conf, "ETCD_INITIAL_CLUSTER_STATE=\"new\"")
-            .expect("Error happened when trying to write `etcd.conf`");
+        EtcdConfig::from(config, &config.instance_ip, &config.instance_name)
+            .write("/opt/etcd/cfg/etcd.conf");
     }
 }
 
@@ -202,14 +150,7 @@ Wants=network-online.target
 [Service]
 Type=notify
 EnvironmentFile=/opt/etcd/cfg/etcd.conf
-ExecStart=/opt/etcd/bin/etcd \
---cert-file=/opt/etcd/ssl/server.pem \
---key-file=/opt/etcd/ssl/server-key.pem \
---peer-cert-file=/opt/etcd/ssl/server.pem \
---peer-key-file=/opt/etcd/ssl/server-key.pem \
---trusted-ca-file=/opt/etcd/ssl/ca.pem \
---peer-trusted-ca-file=/opt/etcd/ssl/ca.pem \
---logger=zap
+ExecStart=/opt/etcd/bin/etcd
 Restart=on-failure
 LimitNOFILE=65536
 
@@ -225,8 +166,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("etcd phase started");
     tracing::info!("Change working directory into `etcd`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/etcd");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("etcd");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `etcd`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -324,12 +265,15 @@ pub fn start(config: &Config) {
             Command::new("scp")
                 .arg("-r")
                 .arg("/opt/etcd")
-                .arg(format!("root@{}:/opt/", ip))
+                .arg(format!("root@{}:/opt/", networking::host(ip)))
                 .status()
                 .expect("Error happened when trying to send files to other worker nodes");
             Command::new("scp")
                 .arg("/usr/lib/systemd/system/etcd.service")
-                .arg(format!("root@{}:/usr/lib/systemd/system/", ip))
+                .arg(format!(
+                    "root@{}:/usr/lib/systemd/system/",
+                    networking::host(ip)
+                ))
                 .status()
                 .expect("Error happened when trying to send files to other worker nodes");
         }
@@ -350,6 +294,7 @@ pub fn start(config: &Config) {
         .arg("etcd")
         .status()
         .expect("Error happened when trying to start `etcd.service`");
+    health::wait_local(config, Component::Etcd);
     tracing::info!("Master is now etcd set");
 
     env::set_current_dir(prev_dir).expect("Error happened when trying to change into `etcd`");
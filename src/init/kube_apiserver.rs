@@ -1,9 +1,13 @@
+use crate::component_config::kube_apiserver;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -109,17 +113,21 @@ impl ServerCsr {
             CN: config.kube_apiserver_CN.to_owned(),
             hosts: {
                 let mut hosts = vec![
-                    "10.0.0.1".to_string(),
+                    networking::api_service_ip(config).to_string(),
                     "127.0.0.1".to_string(),
                     "kubernetes".to_string(),
                     "kubernetes.default".to_string(),
                     "kubernetes.default.svc".to_string(),
                     "kubernetes.default.svc.cluster".to_string(),
-                    "kubernetes.default.svc.cluster.local".to_string(),
+                    format!(
+                        "kubernetes.default.svc.{}",
+                        config.networking.cluster_domain
+                    ),
                 ];
                 for ip in config.instance_hosts.keys() {
                     hosts.push(ip.to_owned());
                 }
+                hosts.extend(networking::endpoint_hosts(config));
                 hosts
             },
             key: Key {
@@ -140,68 +148,11 @@ impl ServerCsr {
 struct KubeApiserverCfg;
 
 impl KubeApiserverCfg {
-    fn generate(config: &Config) {
-        let mut apiserver_conf = File::create("/opt/kubernetes/cfg/kube-apiserver.conf")
-            .expect("Error happened when trying to create kube-apiserver configuration file");
-
-        writeln!(
-            &mut apiserver_conf,
-            r#"KUBE_APISERVER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \"#
-        )
-        .expect("Error happened when trying to write `kube-apiserver.conf`");
-        let mut buffer = String::new();
-        for ip in config.instance_hosts.keys() {
-            buffer.push_str(format!("https://{}:2379,", ip).as_str());
-        }
-        buffer.pop();
-        writeln!(&mut apiserver_conf, "--etcd-servers={}", buffer)
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(&mut apiserver_conf, "--bind-address={}", config.instance_ip)
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(&mut apiserver_conf, "--secure-port=6443")
-            .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(
-            &mut apiserver_conf,
-            "--advertise-address={}",
-            config.instance_ip
-        )
-        .expect("Error happened when trying to write `kube-apiserver.conf`");
-        writeln!(
-            &mut apiserver_conf,
-r#"--allow-privileged=true \
---service-cluster-ip-range=10.0.0.0/24 \
---enable-admission-plugins=NamespaceLifecycle,LimitRanger,ServiceAccount,ResourceQuota,NodeRestriction \
---authorization-mode=RBAC,Node \
---enable-bootstrap-token-auth=true \
---token-auth-file=/opt/kubernetes/cfg/token.csv \
---service-node-port-range=30000-32767 \
---kubelet-client-certificate=/opt/kubernetes/ssl/server.pem \
---kubelet-client-key=/opt/kubernetes/ssl/server-key.pem \
---tls-cert-file=/opt/kubernetes/ssl/server.pem  \
---tls-private-key-file=/opt/kubernetes/ssl/server-key.pem \
---client-ca-file=/opt/kubernetes/ssl/ca.pem \
---service-account-key-file=/opt/kubernetes/ssl/ca-key.pem \
---service-account-issuer=api \
---service-account-signing-key-file=/opt/kubernetes/ssl/server-key.pem \
---etcd-cafile=/opt/etcd/ssl/ca.pem \
---etcd-certfile=/opt/etcd/ssl/server.pem \
---etcd-keyfile=/opt/etcd/ssl/server-key.pem \
---requestheader-client-ca-file=/opt/kubernetes/ssl/ca.pem \
---proxy-client-cert-file=/opt/kubernetes/ssl/server.pem \
---proxy-client-key-file=/opt/kubernetes/ssl/server-key.pem \
---requestheader-allowed-names=kubernetes \
---requestheader-extra-headers-prefix=X-Remote-Extra- \
---requestheader-group-headers=X-Remote-Group \
---requestheader-username-headers=X-Remote-User \
---enable-aggregator-routing=true \
---audit-log-maxage=30 \
---audit-log-maxbackup=3 \
---audit-log-maxsize=100 \
---audit-log-path=/opt/kubernetes/logs/k8s-audit.log""#,
-        )
-        .expect("Error happened when trying to write `kube-apiserver.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_apiserver::flags(config, version, &config.instance_ip).write(
+            "KUBE_APISERVER_OPTS",
+            "/opt/kubernetes/cfg/kube-apiserver.conf",
+        );
     }
 }
 
@@ -233,8 +184,8 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -319,7 +270,7 @@ pub fn start(config: &Config) {
     tracing::info!("ertificates copied");
 
     tracing::info!("Generating `kube-apiserver.conf` to /opt/kubernetes/cfg...");
-    KubeApiserverCfg::generate(config);
+    KubeApiserverCfg::generate(config, &KubeVersion::from(config));
     tracing::info!("`kube-apiserver.conf` generated");
 
     tracing::info!("Generating `token.csv` to /opt/kubernetes/cfg/token.csv...");
@@ -355,9 +306,11 @@ pub fn start(config: &Config) {
         .arg("kube-apiserver")
         .status()
         .expect("Error happened when trying to start `kube-apiserver.service`");
+    health::wait_local(config, Component::Apiserver);
     tracing::info!("Master's apiserver is now set");
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
@@ -1,39 +1,24 @@
+use crate::component_config::kube_controller_manager;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeControllerManagerCfg;
 
 impl KubeControllerManagerCfg {
-    fn generate() {
-        let mut controller_conf = File::create("/opt/kubernetes/cfg/kube-controller-manager.conf")
-            .expect(
-                "Error happened when trying to create kube-controller-manager configuration file",
-            );
-
-        writeln!(
-            &mut controller_conf,
-            r#"KUBE_CONTROLLER_MANAGER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---leader-elect=true \
---kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig \
---bind-address=127.0.0.1 \
---allocate-node-cidrs=true \
---cluster-cidr=10.244.0.0/16 \
---service-cluster-ip-range=10.0.0.0/24 \
---cluster-signing-cert-file=/opt/kubernetes/ssl/ca.pem \
---cluster-signing-key-file=/opt/kubernetes/ssl/ca-key.pem  \
---root-ca-file=/opt/kubernetes/ssl/ca.pem \
---service-account-private-key-file=/opt/kubernetes/ssl/ca-key.pem \
---cluster-signing-duration=87600h0m0s"
-"#
-        )
-        .expect("Error happened when trying to write `kube-controller-manager.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_controller_manager::flags(config, version).write(
+            "KUBE_CONTROLLER_MANAGER_OPTS",
+            "/opt/kubernetes/cfg/kube-controller-manager.conf",
+        );
     }
 }
 
@@ -111,13 +96,13 @@ pub fn start(config: &Config) {
     // kube-controller-manager
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
     tracing::info!("Generating `kube-controller-manager.conf` to /opt/kubernetes/cfg...");
-    KubeControllerManagerCfg::generate();
+    KubeControllerManagerCfg::generate(config, &KubeVersion::from(config));
     tracing::info!("`kube-controller-manager.conf` generated");
 
     tracing::info!("Start generating `kube-controller-manager-csr.json`...");
@@ -152,43 +137,17 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to execute `cfssljson`");
     tracing::info!("Self-signed kube_controller_manager CA certificate generated");
 
-    tracing::info!("Generating `kubeconfig` using `kubectl`");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-cluster")
-        .arg("kubernetes")
-        .arg("--certificate-authority=/opt/kubernetes/ssl/ca.pem")
-        .arg("--embed-certs=true")
-        .arg(format!("--server=https://{}:6443", config.instance_ip))
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-credentials")
-        .arg("kube-controller-manager")
-        .arg("--client-certificate=./kube-controller-manager.pem")
-        .arg("--client-key=./kube-controller-manager-key.pem")
-        .arg("--embed-certs=true")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-context")
-        .arg("default")
-        .arg("--cluster=kubernetes")
-        .arg("--user=kube-controller-manager")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("use-context")
-        .arg("default")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-controller-manager.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
+    tracing::info!("Generating `kube-controller-manager.kubeconfig`...");
+    Kubeconfig::new(
+        &networking::apiserver_url(config, &config.instance_ip),
+        "/opt/kubernetes/ssl/ca.pem",
+        "kube-controller-manager",
+        User::certificate(
+            "kube-controller-manager.pem",
+            "kube-controller-manager-key.pem",
+        ),
+    )
+    .write("/opt/kubernetes/cfg/kube-controller-manager.kubeconfig");
 
     tracing::info!("Generating `kube-controller-manager.service` to /usr/lib/systemd/system/");
     KubeControllerManagerUnit::generate();
@@ -208,9 +167,11 @@ pub fn start(config: &Config) {
         .arg("kube-controller-manager")
         .status()
         .expect("Error happened when trying to start `kube-controller-manager.service`");
+    health::wait_local(config, Component::ControllerManager);
     tracing::info!("Master's controller manager is now set");
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
@@ -1,4 +1,7 @@
 use crate::config::Config;
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
@@ -56,8 +59,8 @@ pub fn start(config: &Config) {
     // kube-ctl
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -98,43 +101,14 @@ pub fn start(config: &Config) {
             .expect("Error happened when trying to create `.kube` directory");
     }
 
-    tracing::info!("Generating `kubeconfig` using `kubectl`");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-cluster")
-        .arg("kubernetes")
-        .arg("--certificate-authority=/opt/kubernetes/ssl/ca.pem")
-        .arg("--embed-certs=true")
-        .arg(format!("--server=https://{}:6443", config.instance_ip))
-        .arg("--kubeconfig=/root/.kube/config")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-credentials")
-        .arg("cluster-admin")
-        .arg("--client-certificate=./admin.pem")
-        .arg("--client-key=./admin-key.pem")
-        .arg("--embed-certs=true")
-        .arg("--kubeconfig=/root/.kube/config")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-context")
-        .arg("default")
-        .arg("--cluster=kubernetes")
-        .arg("--user=cluster-admin")
-        .arg("--kubeconfig=/root/.kube/config")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("use-context")
-        .arg("default")
-        .arg("--kubeconfig=/root/.kube/config")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
+    tracing::info!("Generating `/root/.kube/config`...");
+    Kubeconfig::new(
+        &networking::apiserver_url(config, &config.instance_ip),
+        "/opt/kubernetes/ssl/ca.pem",
+        "cluster-admin",
+        User::certificate("admin.pem", "admin-key.pem"),
+    )
+    .write("/root/.kube/config");
 
     Command::new("kubectl")
         .arg("create")
@@ -146,7 +120,8 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to execute kubectl");
     tracing::info!("Master's scheduler is now set");
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
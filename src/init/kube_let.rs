@@ -1,85 +1,38 @@
+use crate::component_config::{self, kubelet, kubelet::KubeletConfiguration};
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::registry;
+use crate::runtime::Runtime;
+use crate::version::KubeVersion;
+use crate::workdir;
 use regex::Regex;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
 use std::process::Command;
+use std::{thread, time};
 
 struct KubeletCfg;
 
 impl KubeletCfg {
-    fn generate(config: &Config) {
-        let mut kubelet_conf = File::create("/opt/kubernetes/cfg/kubelet.conf")
-            .expect("Error happened when trying to create kubelet configuration file");
-
-        writeln!(
-            &mut kubelet_conf,
-            r#"KUBELET_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \"#,
-        )
-        .expect("Error happened when trying to write `kubelet.conf`");
-        writeln!(
-            &mut kubelet_conf,
-            "--hostname-override={} \\",
-            config.instance_name
-        )
-        .expect("Error happened when trying to write `kubelet.conf`");
-        writeln!(
-            &mut kubelet_conf,
-            r#"--network-plugin=cni \
---kubeconfig=/opt/kubernetes/cfg/kubelet.kubeconfig \
---bootstrap-kubeconfig=/opt/kubernetes/cfg/bootstrap.kubeconfig \
---config=/opt/kubernetes/cfg/kubelet-config.yml \
---cert-dir=/opt/kubernetes/ssl \
---pod-infra-container-image=registry.cn-hangzhou.aliyuncs.com/google-containers/pause-amd64:3.0"
-"#
-        )
-        .expect("Error happened when trying to write `kubelet.conf`");
+    fn generate(config: &Config, version: &KubeVersion, runtime: Runtime) {
+        kubelet::flags(config, version, runtime, &config.instance_name)
+            .write("KUBELET_OPTS", "/opt/kubernetes/cfg/kubelet.conf");
     }
 }
 
 struct KubeletConfig;
 
 impl KubeletConfig {
-    fn generate() {
-        let mut kubelet_config = File::create("/opt/kubernetes/cfg/kubelet-config.yml")
-            .expect("Error happened when trying to create kubelet configuration file");
-
-        writeln!(
-            &mut kubelet_config,
-            r#"kind: KubeletConfiguration
-apiVersion: kubelet.config.k8s.io/v1beta1
-address: 0.0.0.0
-port: 10250
-readOnlyPort: 10255
-cgroupDriver: systemd
-clusterDNS:
-- 10.0.0.2
-clusterDomain: cluster.local 
-failSwapOn: false
-authentication:
-  anonymous:
-    enabled: false
-  webhook:
-    cacheTTL: 2m0s
-    enabled: true
-  x509:
-    clientCAFile: /opt/kubernetes/ssl/ca.pem 
-authorization:
-  mode: Webhook
-  webhook:
-    cacheAuthorizedTTL: 5m0s
-    cacheUnauthorizedTTL: 30s
-evictionHard:
-  imagefs.available: 15%
-  memory.available: 100Mi
-  nodefs.available: 10%
-  nodefs.inodesFree: 5%
-maxOpenFiles: 1000000
-maxPods: 110
-"#,
+    fn generate(config: &Config, version: &KubeVersion, runtime: Runtime) {
+        fs::write(
+            "/opt/kubernetes/cfg/kubelet-config.yml",
+            component_config::to_yaml(
+                &KubeletConfiguration::from(config, version, runtime),
+                &config.components.kubelet.config_patch,
+            ),
         )
         .expect("Error happened when trying to write `kubelet-config.yml`");
     }
@@ -88,12 +41,13 @@ maxPods: 110
 struct KubeletUnit;
 
 impl KubeletUnit {
-    fn generate() {
+    fn generate(runtime: Runtime) {
         let mut kubelet_unit = File::create("/usr/lib/systemd/system/kubelet.service")
             .expect("Error happened when trying to create kubelet unit file");
-        let content = r#"[Unit]
+        let content = format!(
+            r#"[Unit]
 Description=Kubernetes Kubelet
-After=docker.service
+After={}
 
 [Service]
 EnvironmentFile=/opt/kubernetes/cfg/kubelet.conf
@@ -103,7 +57,9 @@ LimitNOFILE=65536
 
 [Install]
 WantedBy=multi-user.target
-"#;
+"#,
+            runtime.unit()
+        );
         kubelet_unit
             .write_all(content.as_bytes())
             .expect("Error happened when trying to write kubelet unit file");
@@ -114,57 +70,41 @@ pub fn start(config: &Config) {
     // kube-scheduler
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
+    let version = KubeVersion::from(config);
+    let runtime = Runtime::from(config);
     tracing::info!("Generating `kubelet.conf` to /opt/kubernetes/cfg...");
-    KubeletCfg::generate(config);
+    KubeletCfg::generate(config, &version, runtime);
     tracing::info!("`kubelet.conf` generated");
 
     tracing::info!("Generating `kubelet-config.yml` to /opt/kubernetes/cfg...");
-    KubeletConfig::generate();
+    KubeletConfig::generate(config, &version, runtime);
     tracing::info!("`kubelet-config.yml` generated");
 
-    tracing::info!("Generating `kubeconfig` using `kubectl`");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-cluster")
-        .arg("kubernetes")
-        .arg("--certificate-authority=/opt/kubernetes/ssl/ca.pem")
-        .arg("--embed-certs=true")
-        .arg(format!("--server=https://{}:6443", config.instance_ip))
-        .arg("--kubeconfig=/opt/kubernetes/cfg/bootstrap.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-credentials")
-        .arg("kubelet-bootstrap")
-        .arg("--token=4136692876ad4b01bb9dd0988480ebba")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/bootstrap.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-context")
-        .arg("default")
-        .arg("--cluster=kubernetes")
-        .arg("--user=kubelet-bootstrap")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/bootstrap.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("use-context")
-        .arg("default")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/bootstrap.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
+    // Kubelet hands these credentials to the runtime when pulling pod images.
+    if let Some(auths) = registry::auth_json(config) {
+        fs::create_dir_all("/var/lib/kubelet")
+            .expect("Error happened when trying to create `/var/lib/kubelet`");
+        fs::write("/var/lib/kubelet/config.json", auths)
+            .expect("Error happened when trying to write kubelet `config.json`");
+        tracing::info!("Kubelet `config.json` generated");
+    }
+
+    tracing::info!("Generating `bootstrap.kubeconfig`...");
+    Kubeconfig::new(
+        &networking::apiserver_url(config, &config.instance_ip),
+        "/opt/kubernetes/ssl/ca.pem",
+        "kubelet-bootstrap",
+        User::token("4136692876ad4b01bb9dd0988480ebba"),
+    )
+    .write("/opt/kubernetes/cfg/bootstrap.kubeconfig");
 
     tracing::info!("Generating `kubelet.service` to /usr/lib/systemd/system/");
-    KubeletUnit::generate();
+    KubeletUnit::generate(runtime);
     tracing::info!("`kubelet.service` generated");
 
     Command::new("systemctl")
@@ -183,45 +123,45 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to start `kubelet.service`");
     tracing::info!("Master's kubelet is now set");
 
-    loop {
+    // kubectl approve master node csr.
+    let csr = Regex::new(r"node-csr-\S*").unwrap();
+    let timeout = Component::Kubelet.timeout(config);
+    let deadline = time::Instant::now() + time::Duration::from_secs(timeout);
+    let output = loop {
         let output = Command::new("kubectl")
             .arg("get")
             .arg("csr")
             .output()
             .unwrap()
             .stdout;
-        if !output.is_empty() {
-            break;
+        let output = String::from_utf8(output).unwrap();
+        if csr.is_match(&output) {
+            break output;
         }
-        tracing::info!("Waiting other etcd nodes to join cluster");
-    }
-
-    // kubectl approve master node csr.
-    let output = Command::new("kubectl")
-        .arg("get")
-        .arg("csr")
-        .output()
-        .unwrap()
-        .stdout;
-    let output = String::from_utf8(output).unwrap();
-    tracing::info!("Output of kubectl is: {}", output);
-    let csr = Regex::new(r"node-csr-\S*").unwrap();
-    let mut res = "";
-    for word in output.split_whitespace() {
-        if csr.is_match(word) {
-            res = word;
-            break;
+        if time::Instant::now() >= deadline {
+            panic!(
+                "Kubelet did not request a certificate within {}s, its last logs are:\n{}",
+                timeout,
+                health::journal(None, "kubelet")
+            );
         }
+        tracing::info!("Waiting for kubelet to request a certificate");
+        thread::sleep(time::Duration::from_secs(1));
+    };
+    tracing::info!("Output of kubectl is: {}", output);
+    for res in csr.find_iter(&output) {
+        tracing::info!("Retrieved csr is: {}", res.as_str());
+        Command::new("kubectl")
+            .arg("certificate")
+            .arg("approve")
+            .arg(res.as_str())
+            .status()
+            .expect("Error happened when trying to approve csr from node");
     }
-    tracing::info!("Retrieved csr is: {}", res);
-    Command::new("kubectl")
-        .arg("certificate")
-        .arg("approve")
-        .arg(res)
-        .status()
-        .expect("Error happened when trying to approve csr from node");
+    health::wait_local(config, Component::Kubelet);
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
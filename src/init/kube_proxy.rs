@@ -1,57 +1,34 @@
+use crate::component_config::{self, kube_proxy, kube_proxy::KubeProxyConfiguration};
 use crate::config::Config;
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeProxyCfg;
 
 impl KubeProxyCfg {
-    fn generate() {
-        let mut kube_proxy_conf = File::create("/opt/kubernetes/cfg/kube-proxy.conf")
-            .expect("Error happened when trying to create kube-proxy configuration file");
-
-        writeln!(
-            &mut kube_proxy_conf,
-            r#"KUBE_PROXY_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---config=/opt/kubernetes/cfg/kube-proxy-config.yml"
-"#
-        )
-        .expect("Error happened when trying to write `kube-proxy.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_proxy::flags(config, version)
+            .write("KUBE_PROXY_OPTS", "/opt/kubernetes/cfg/kube-proxy.conf");
     }
 }
 
 struct KubeProxyConfig;
 
 impl KubeProxyConfig {
-    fn generate(config: &Config) {
-        let mut kube_proxy_config = File::create("/opt/kubernetes/cfg/kube-proxy-config.yml")
-            .expect("Error happened when trying to create kube-proxy configuration file");
-
-        writeln!(
-            &mut kube_proxy_config,
-            r#"kind: KubeProxyConfiguration
-apiVersion: kubeproxy.config.k8s.io/v1alpha1
-bindAddress: 0.0.0.0
-metricsBindAddress: 0.0.0.0:10249
-clientConnection:
-  kubeconfig: /opt/kubernetes/cfg/kube-proxy.kubeconfig"#
-        )
-        .expect("Error happened when trying to write `kube-proxy-config.yml`");
-        writeln!(
-            &mut kube_proxy_config,
-            "hostnameOverride: {} \\",
-            config.instance_name
-        )
-        .expect("Error happened when trying to write `kube-proxy-config.yml`");
-        writeln!(
-            &mut kube_proxy_config,
-            r#"clusterCIDR: 10.244.0.0/16
-"#,
+    fn generate(config: &Config, version: &KubeVersion) {
+        fs::write(
+            "/opt/kubernetes/cfg/kube-proxy-config.yml",
+            component_config::to_yaml(
+                &KubeProxyConfiguration::from(config, version, &config.instance_name),
+                &config.components.kube_proxy.config_patch,
+            ),
         )
         .expect("Error happened when trying to write `kube-proxy-config.yml`");
     }
@@ -131,8 +108,8 @@ pub fn start(config: &Config) {
     // kube-proxy
     tracing::info!("kube_proxy phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
@@ -167,50 +144,22 @@ pub fn start(config: &Config) {
     tracing::info!("Self-signed kube_proxy CA certificate generated");
 
     tracing::info!("Generating `kube-proxy.conf` to /opt/kubernetes/cfg...");
-    KubeProxyCfg::generate();
+    let version = KubeVersion::from(config);
+    KubeProxyCfg::generate(config, &version);
     tracing::info!("`kube-proxy.conf` generated");
 
     tracing::info!("Generating `kube-proxy-config.yml` to /opt/kubernetes/cfg...");
-    KubeProxyConfig::generate(config);
+    KubeProxyConfig::generate(config, &version);
     tracing::info!("`kube-proxy-config.yml` generated");
 
-    tracing::info!("Generating `kubeconfig` using `kubectl`");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-cluster")
-        .arg("kubernetes")
-        .arg("--certificate-authority=/opt/kubernetes/ssl/ca.pem")
-        .arg("--embed-certs=true")
-        .arg(format!("--server=https://{}:6443", config.instance_ip))
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-credentials")
-        .arg("kube-proxy")
-        .arg("--client-certificate=./kube-proxy.pem")
-        .arg("--client-key=./kube-proxy-key.pem")
-        .arg("--embed-certs=true")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-context")
-        .arg("default")
-        .arg("--cluster=kubernetes")
-        .arg("--user=kube-proxy")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("use-context")
-        .arg("default")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-proxy.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
+    tracing::info!("Generating `kube-proxy.kubeconfig`...");
+    Kubeconfig::new(
+        &networking::apiserver_url(config, &config.instance_ip),
+        "/opt/kubernetes/ssl/ca.pem",
+        "kube-proxy",
+        User::certificate("kube-proxy.pem", "kube-proxy-key.pem"),
+    )
+    .write("/opt/kubernetes/cfg/kube-proxy.kubeconfig");
 
     tracing::info!("Generating `kube-proxy.service` to /usr/lib/systemd/system/");
     KubeProxyUnit::generate();
@@ -232,21 +181,8 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to start `kube-proxy.service`");
     tracing::info!("Master's proxy is now set");
 
-    tracing::info!("Deploying Calico...");
-    Command::new("curl")
-        .arg("https://docs.projectcalico.org/v3.20/manifests/calico.yaml")
-        .arg("-o")
-        .arg("calico.yaml")
-        .status()
-        .expect("Error happened when trying to download Calico");
-    Command::new("kubectl")
-        .arg("apply")
-        .arg("-f")
-        .arg("calico.yaml")
-        .status()
-        .expect("Error happened when trying to apply calico.yaml");
-
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
@@ -1,29 +1,24 @@
+use crate::component_config::kube_scheduler;
 use crate::config::Config;
+use crate::health::{self, Component};
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::version::KubeVersion;
+use crate::workdir;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::process::{Command, Stdio};
 
 struct KubeSchedulerCfg;
 
 impl KubeSchedulerCfg {
-    fn generate() {
-        let mut scheduler_conf = File::create("/opt/kubernetes/cfg/kube-scheduler.conf")
-            .expect("Error happened when trying to create kube-scheduler configuration file");
-
-        writeln!(
-            &mut scheduler_conf,
-            r#"KUBE_SCHEDULER_OPTS="--logtostderr=false \
---v=2 \
---log-dir=/opt/kubernetes/logs \
---leader-elect \
---kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig \
---bind-address=127.0.0.1"
-"#
-        )
-        .expect("Error happened when trying to write `kube-controller-manager.conf`");
+    fn generate(config: &Config, version: &KubeVersion) {
+        kube_scheduler::flags(config, version).write(
+            "KUBE_SCHEDULER_OPTS",
+            "/opt/kubernetes/cfg/kube-scheduler.conf",
+        );
     }
 }
 
@@ -100,13 +95,13 @@ pub fn start(config: &Config) {
     // kube-scheduler
     tracing::info!("kube_apiserver phase started");
     tracing::info!("Change working directory into `k8s`");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/k8s");
+    let prev_dir = workdir::home();
+    let work_dir = &workdir::pki("k8s");
     env::set_current_dir(work_dir).expect("Error happened when trying to change into `k8s`");
     tracing::info!("Changed to {}", env::current_dir().unwrap().display());
 
     tracing::info!("Generating `kube-scheduler.conf` to /opt/kubernetes/cfg...");
-    KubeSchedulerCfg::generate();
+    KubeSchedulerCfg::generate(config, &KubeVersion::from(config));
     tracing::info!("`kube-scheduler.conf` generated");
 
     tracing::info!("Start generating `kube-scheduler-csr.json`...");
@@ -139,43 +134,14 @@ pub fn start(config: &Config) {
         .expect("Error happened when trying to execute `cfssljson`");
     tracing::info!("Self-signed kube_scheduler CA certificate generated");
 
-    tracing::info!("Generating `kubeconfig` using `kubectl`");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-cluster")
-        .arg("kubernetes")
-        .arg("--certificate-authority=/opt/kubernetes/ssl/ca.pem")
-        .arg("--embed-certs=true")
-        .arg(format!("--server=https://{}:6443", config.instance_ip))
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-credentials")
-        .arg("kube-scheduler")
-        .arg("--client-certificate=./kube-scheduler.pem")
-        .arg("--client-key=./kube-scheduler-key.pem")
-        .arg("--embed-certs=true")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("set-context")
-        .arg("default")
-        .arg("--cluster=kubernetes")
-        .arg("--user=kube-scheduler")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
-    Command::new("kubectl")
-        .arg("config")
-        .arg("use-context")
-        .arg("default")
-        .arg("--kubeconfig=/opt/kubernetes/cfg/kube-scheduler.kubeconfig")
-        .status()
-        .expect("Error happened when trying to execute kubectl");
+    tracing::info!("Generating `kube-scheduler.kubeconfig`...");
+    Kubeconfig::new(
+        &networking::apiserver_url(config, &config.instance_ip),
+        "/opt/kubernetes/ssl/ca.pem",
+        "kube-scheduler",
+        User::certificate("kube-scheduler.pem", "kube-scheduler-key.pem"),
+    )
+    .write("/opt/kubernetes/cfg/kube-scheduler.kubeconfig");
 
     tracing::info!("Generating `kube-scheduler.service` to /usr/lib/systemd/system/");
     KubeSchedulerUnit::generate();
@@ -195,9 +161,11 @@ pub fn start(config: &Config) {
         .arg("kube-scheduler")
         .status()
         .expect("Error happened when trying to start `kube-scheduler.service`");
+    health::wait_local(config, Component::Scheduler);
     tracing::info!("Master's scheduler is now set");
 
-    env::set_current_dir(prev_dir).expect("Error happened when trying to change into `/rk8s`");
+    env::set_current_dir(prev_dir)
+        .expect("Error happened when trying to change into working directory");
     tracing::info!(
         "Change working directory back to {}",
         env::current_dir().unwrap().display()
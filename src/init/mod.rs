@@ -1,3 +1,5 @@
+pub mod cni;
+pub mod coredns;
 pub mod etcd;
 pub mod kube_apiserver;
 pub mod kube_controller_manager;
@@ -1,4 +1,6 @@
 use crate::config::Config;
+use crate::networking;
+use crate::proxy::{self, ProxyMode};
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
@@ -65,14 +67,32 @@ pub fn start(config: &Config) {
     }
     tracing::info!("`/etc/hosts` set");
 
-    // Set IPv4 iptables.
+    // Load kernel modules, needed before the bridge knobs can be set.
+    tracing::info!("Setting `/etc/modules-load.d/k8s.conf` according to configuration...");
+    let mut k8s_module = File::create("/etc/modules-load.d/k8s.conf")
+        .expect("Error happened when trying to create `k8s.conf` file");
+    k8s_module
+        .write_all(proxy::modules_load_conf(config).as_bytes())
+        .expect("Error happened when trying to write to `/etc/modules-load.d/k8s.conf`");
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(proxy::preflight_command(config))
+        .status()
+        .expect("Error happened when trying to load kernel modules");
+    if !status.success() {
+        panic!(
+            "This machine is missing what kube-proxy needs in `{}` mode",
+            ProxyMode::from(config)
+        );
+    }
+    tracing::info!("`/etc/modules-load.d/k8s.conf` set");
+
+    // Set bridge netfilter and forwarding.
     tracing::info!("Setting `/etc/sysctl.d/k8s.conf` according to configuration...");
     let mut k8s_conf = File::create("/etc/sysctl.d/k8s.conf")
         .expect("Error happened when trying to create `k8s.conf` file");
     k8s_conf
-        .write_all(
-            b"net.bridge.bridge-nf-call-ip6tables = 1\nnet.bridge.bridge-nf-call-iptables = 1\n",
-        )
+        .write_all(networking::sysctl_conf(config).as_bytes())
         .expect("Error happened when trying to write to `/etc/sysctl.d/k8s.conf`");
     Command::new("sysctl")
         .arg("--system")
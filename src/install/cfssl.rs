@@ -1,59 +1,31 @@
-use std::env;
-use std::path::Path;
+use crate::artifact;
+use crate::config::Config;
+use crate::workdir;
 use std::process::Command;
 
-pub fn start() {
-    tracing::info!("Start Installing cfssl...");
-    let prev_dir = Path::new("/rk8s");
-    let work_dir = Path::new("/rk8s/preparation");
-    env::set_current_dir(work_dir)
-        .expect("Error happened when trying to change into `praparation`");
-    tracing::info!("Changed to {}", env::current_dir().unwrap().display());
-    Command::new("cp")
-        .arg("cfssl")
-        .arg("/usr/local/bin/cfssl")
-        .status()
-        .expect("Error happened when trying to copy `cfssl`");
-    tracing::info!("cfssl copied");
-    Command::new("chmod")
-        .arg("+x")
-        .arg("/usr/local/bin/cfssl")
-        .status()
-        .expect("Error happened when trying to set `cfssl` executable");
-    tracing::info!("cfssl is ready");
-
-    tracing::info!("Start installing cfssljson...");
-    Command::new("cp")
-        .arg("cfssljson")
-        .arg("/usr/local/bin/cfssljson")
-        .status()
-        .expect("Error happened when trying to copy `cfssljson`");
-    tracing::info!("cfssljson copied");
-    Command::new("chmod")
-        .arg("+x")
-        .arg("/usr/local/bin/cfssljson")
-        .status()
-        .expect("Error happened when trying to set `cfssljson` executable");
-    tracing::info!("cfssljson is ready");
-
-    tracing::info!("Start installing cfsslcertinfo...");
-    Command::new("cp")
-        .arg("cfssl-certinfo")
-        .arg("/usr/local/bin/cfssl-certinfo")
-        .status()
-        .expect("Error happened when trying to copy `cfssl-certinfo`");
-    tracing::info!("cfssl-certinfo copied");
-    Command::new("chmod")
-        .arg("+x")
-        .arg("/usr/local/bin/cfssl-certinfo")
-        .status()
-        .expect("Error happened when trying to set `cfssl-certinfo` executable");
-    tracing::info!("cfssl-certinfo is ready");
-
-    env::set_current_dir(prev_dir)
-        .expect("Error happened when trying to change into `praparation`");
-    tracing::info!(
-        "Change working directory back to {}",
-        env::current_dir().unwrap().display()
-    );
+pub fn start(config: &Config) {
+    for (file, url, expected) in artifact::cfssl_tools(config) {
+        tracing::info!("Start installing {}...", file);
+        // Verified like release archives, these sign every certificate.
+        let prepared = workdir::preparation().join(file);
+        if prepared.is_file() {
+            artifact::verify(&prepared, &expected);
+        } else {
+            tracing::info!("{} not prepared, downloading {}", prepared.display(), url);
+            artifact::download(&url, &prepared, &expected);
+        }
+        let installed = format!("/usr/local/bin/{}", file);
+        Command::new("cp")
+            .arg(&prepared)
+            .arg(&installed)
+            .status()
+            .unwrap_or_else(|_| panic!("Error happened when trying to copy `{}`", file));
+        tracing::info!("{} copied", file);
+        Command::new("chmod")
+            .arg("+x")
+            .arg(&installed)
+            .status()
+            .unwrap_or_else(|_| panic!("Error happened when trying to set `{}` executable", file));
+        tracing::info!("{} is ready", file);
+    }
 }
@@ -0,0 +1,161 @@
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::arch::Arch;
+use crate::artifact::{self, Artifact};
+use crate::config::Config;
+use crate::registry;
+
+struct ContainerdCfg;
+
+impl ContainerdCfg {
+    fn generate(config: &Config) {
+        let mut containerd_cfg = File::create("/etc/containerd/config.toml")
+            .expect("Error happened when trying to create containerd config file");
+        let content = format!(
+            r#"version = 2
+
+[plugins]
+  [plugins."io.containerd.grpc.v1.cri"]
+    sandbox_image = "{}"
+    [plugins."io.containerd.grpc.v1.cri".containerd]
+      default_runtime_name = "runc"
+      [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.runc]
+        runtime_type = "io.containerd.runc.v2"
+        [plugins."io.containerd.grpc.v1.cri".containerd.runtimes.runc.options]
+          SystemdCgroup = true
+    [plugins."io.containerd.grpc.v1.cri".cni]
+      bin_dir = "/opt/cni/bin"
+      conf_dir = "/etc/cni/net.d"
+{}"#,
+            config.sandbox_image,
+            registry::containerd_registry_toml(config)
+        );
+        containerd_cfg
+            .write_all(content.as_bytes())
+            .expect("Error happened when trying to write containerd config file");
+    }
+}
+
+struct ContainerdUnit;
+
+impl ContainerdUnit {
+    fn generate() {
+        let mut containerd_unit = File::create("/usr/lib/systemd/system/containerd.service")
+            .expect("Error happened when trying to create containerd unit file");
+        let content = r#"[Unit]
+Description=containerd container runtime
+Documentation=https://containerd.io
+After=network.target local-fs.target
+
+[Service]
+ExecStartPre=-/sbin/modprobe overlay
+ExecStart=/usr/bin/containerd
+Type=notify
+Delegate=yes
+KillMode=process
+Restart=always
+RestartSec=5
+LimitNPROC=infinity
+LimitCORE=infinity
+LimitNOFILE=infinity
+TasksMax=infinity
+OOMScoreAdjust=-999
+
+[Install]
+WantedBy=multi-user.target
+"#;
+        containerd_unit
+            .write_all(content.as_bytes())
+            .expect("Error happened when trying to write containerd unit file");
+    }
+}
+
+pub fn start(config: &Config) {
+    tracing::info!("Start installing containerd");
+    check_dir_exist_or_create(PathBuf::from("containerd"));
+    tracing::info!("Downloading containerd binary from containerd_URL");
+    let containerd = Artifact::containerd(config, Arch::local());
+    artifact::download(
+        &containerd.url,
+        &PathBuf::from(format!("containerd/{}", containerd.file)),
+        &containerd.expected_sha256(),
+    );
+    tracing::info!("Downloading runc binary from runc_URL");
+    let runc = Artifact::runc(config, Arch::local());
+    artifact::download(
+        &runc.url,
+        &PathBuf::from(format!("containerd/{}", runc.file)),
+        &runc.expected_sha256(),
+    );
+
+    tracing::info!("untaring downloaded file");
+    Command::new("tar")
+        .arg("-zxf")
+        .arg(format!("containerd/{}", containerd.file))
+        .arg("--directory")
+        .arg("containerd")
+        .status()
+        .expect("Error happened when trying to untar `containerd` executable");
+    let binaries: Vec<PathBuf> = fs::read_dir(format!("containerd/{}", containerd.dir))
+        .expect("Error happened when trying to read untared `containerd` folder")
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    Command::new("cp")
+        .args(&binaries)
+        .arg("/usr/bin")
+        .status()
+        .expect("Error happened when trying to copy `containerd` executable to `/usr/bin`");
+    Command::new("install")
+        .arg("-m")
+        .arg("755")
+        .arg(format!("containerd/{}", runc.file))
+        .arg("/usr/bin/runc")
+        .status()
+        .expect("Error happened when trying to copy `runc` executable to `/usr/bin`");
+
+    tracing::info!("Generating containerd.service file to /usr/lib/systemd/system/");
+    ContainerdUnit::generate();
+    tracing::info!("containerd.service generated");
+
+    check_dir_exist_or_create(PathBuf::from("/etc/containerd"));
+    tracing::info!("Generating config.toml to /etc/containerd/");
+    ContainerdCfg::generate(config);
+    tracing::info!("config.toml generated");
+    for host in registry::hosts(config) {
+        let dir = PathBuf::from("/etc/containerd/certs.d").join(&host);
+        check_dir_exist_or_create(dir.clone());
+        fs::write(
+            dir.join("hosts.toml"),
+            registry::containerd_hosts_toml(config, &host),
+        )
+        .expect("Error happened when trying to write `hosts.toml`");
+    }
+
+    Command::new("systemctl")
+        .arg("daemon-reload")
+        .status()
+        .expect("Error happened when trying to reload systemd daemons");
+    Command::new("systemctl")
+        .arg("enable")
+        .arg("containerd")
+        .status()
+        .expect("Error happened when trying to enable `containerd.service`");
+    Command::new("systemctl")
+        .arg("start")
+        .arg("containerd")
+        .status()
+        .expect("Error happened when trying to start `containerd.service`");
+
+    tracing::info!("containerd is now installed");
+}
+
+fn check_dir_exist_or_create(path: PathBuf) {
+    if !path.is_dir() {
+        fs::create_dir_all(path).expect("Error happened when trying to create path");
+    }
+}
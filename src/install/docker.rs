@@ -4,7 +4,10 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
 
+use crate::arch::Arch;
+use crate::artifact::{self, Artifact};
 use crate::config::Config;
+use crate::workdir;
 
 struct DockerCfg;
 
@@ -65,36 +68,36 @@ WantedBy=multi-user.target
 pub fn start(config: &Config) {
     tracing::info!("Start installing docker");
     tracing::info!("Downloading docker binary from docker_URL");
-    Command::new("curl")
-        .arg("-L")
-        .arg(&config.docker_url)
-        .arg("-o")
-        .arg("docker/docker-20.10.9.tgz")
-        .status()
-        .expect("Error happened when trying to download `etcd`");
-
-    if PathBuf::from("/rk8s/docker/docker-20.10.9.tgz").is_file() {
+    let docker = Artifact::docker(config, Arch::local());
+    artifact::download(
+        &docker.url,
+        &PathBuf::from(format!("docker/{}", docker.file)),
+        &docker.expected_sha256(),
+    );
+
+    if workdir::home()
+        .join(format!("docker/{}", docker.file))
+        .is_file()
+    {
         tracing::info!("docker downloaded");
 
         tracing::info!("untaring downloaded file");
         Command::new("tar")
             .arg("-zxf")
-            .arg("docker/docker-20.10.9.tgz")
+            .arg(format!("docker/{}", docker.file))
             .arg("--directory")
             .arg("docker")
             .status()
             .expect("Error happened when trying to untar `docker` executable");
 
+        // The set of binaries shipped varies between docker releases.
+        let binaries: Vec<PathBuf> = fs::read_dir(format!("docker/{}", docker.dir))
+            .expect("Error happened when trying to read untared `docker` folder")
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
         Command::new("cp")
-            .arg("docker/docker/containerd")
-            .arg("docker/docker/containerd-shim")
-            .arg("docker/docker/containerd-shim-runc-v2")
-            .arg("docker/docker/ctr")
-            .arg("docker/docker/docker")
-            .arg("docker/docker/dockerd")
-            .arg("docker/docker/docker-init")
-            .arg("docker/docker/docker-proxy")
-            .arg("docker/docker/runc")
+            .args(&binaries)
             .arg("/usr/bin")
             .status()
             .expect("Error happened when trying to copy `docker` executable to `/usr/bin`");
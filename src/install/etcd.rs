@@ -1,29 +1,29 @@
+use crate::arch::Arch;
+use crate::artifact::{self, Artifact};
 use crate::config::Config;
+use crate::workdir;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
 pub fn start(config: &Config) {
     tracing::info!("Start downloading etcd...");
-    // Retrieve file name from URL.
-    let fields: Vec<&str> = config.etcd_url.as_str().split('/').collect();
-    let filename = fields[fields.len() - 1];
-    let fields: Vec<&str> = filename.split(".tar.").collect();
-    let folder_name = fields[0];
-    Command::new("curl")
-        .arg("-L")
-        .arg(&config.etcd_url)
-        .arg("-o")
-        .arg(format!("etcd/{}.tar.gz", folder_name))
-        .status()
-        .expect("Error happened when trying to download `etcd`");
-    if PathBuf::from(format!("/rk8s/etcd/{}.tar.gz", folder_name)).is_file() {
+    let etcd = Artifact::etcd(config, Arch::local());
+    artifact::download(
+        &etcd.url,
+        &PathBuf::from(format!("etcd/{}", etcd.file)),
+        &etcd.expected_sha256(),
+    );
+    if workdir::home()
+        .join(format!("etcd/{}", etcd.file))
+        .is_file()
+    {
         tracing::info!("etcd downloaded");
 
         tracing::info!("untaring downloaded file");
         Command::new("tar")
             .arg("-zxf")
-            .arg(format!("etcd/{}.tar.gz", folder_name))
+            .arg(format!("etcd/{}", etcd.file))
             .arg("--directory")
             .arg("etcd")
             .status()
@@ -37,8 +37,8 @@ pub fn start(config: &Config) {
         check_dir_exist_or_create(ssl_path);
 
         Command::new("cp")
-            .arg(format!("etcd/{}/etcd", folder_name))
-            .arg(format!("etcd/{}/etcdctl", folder_name))
+            .arg(format!("etcd/{}/etcd", etcd.dir))
+            .arg(format!("etcd/{}/etcdctl", etcd.dir))
             .arg("/opt/etcd/bin")
             .status()
             .expect("Error happened when trying to copy `etcd` executable to `/opt/etcd/bin`");
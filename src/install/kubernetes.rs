@@ -1,26 +1,29 @@
+use crate::arch::Arch;
+use crate::artifact::{self, Artifact};
 use crate::config::Config;
+use crate::workdir;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
 pub fn start(config: &Config) {
     tracing::info!("Start downloading kubernetes...");
-    // Retrieve file name from URL.
-    let filename = "kubernetes-server-linux-amd64";
-    Command::new("curl")
-        .arg("-L")
-        .arg(&config.kubernetes_url)
-        .arg("-o")
-        .arg(format!("k8s/{}.tar.gz", filename))
-        .status()
-        .expect("Error happened when trying to download `kubernetes`");
-    if PathBuf::from(format!("/rk8s/k8s/{}.tar.gz", filename)).is_file() {
+    let kubernetes = Artifact::kubernetes(config, Arch::local());
+    artifact::download(
+        &kubernetes.url,
+        &PathBuf::from(format!("k8s/{}", kubernetes.file)),
+        &kubernetes.expected_sha256(),
+    );
+    if workdir::home()
+        .join(format!("k8s/{}", kubernetes.file))
+        .is_file()
+    {
         tracing::info!("kubernetes downloaded");
 
         tracing::info!("untaring downloaded file");
         Command::new("tar")
             .arg("-zxf")
-            .arg(format!("k8s/{}.tar.gz", filename))
+            .arg(format!("k8s/{}", kubernetes.file))
             .arg("--directory")
             .arg("k8s")
             .status()
@@ -36,11 +39,11 @@ pub fn start(config: &Config) {
         check_dir_exist_or_create(logs_path);
 
         Command::new("cp")
-            .arg("k8s/kubernetes/server/bin/kube-apiserver")
-            .arg("k8s/kubernetes/server/bin/kube-scheduler")
-            .arg("k8s/kubernetes/server/bin/kube-controller-manager")
-            .arg("k8s/kubernetes/server/bin/kube-proxy")
-            .arg("k8s/kubernetes/server/bin/kubelet")
+            .arg(format!("k8s/{}/kube-apiserver", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-scheduler", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-controller-manager", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-proxy", kubernetes.dir))
+            .arg(format!("k8s/{}/kubelet", kubernetes.dir))
             .arg("/opt/kubernetes/bin")
             .status()
             .expect("Error happened when trying to copy `kubernetes` executable to `/opt/kubernetes/bin`");
@@ -55,23 +58,23 @@ pub fn start(config: &Config) {
         let kubernetes_logs = PathBuf::from("to_send/kubernetes/logs");
         check_dir_exist_or_create(kubernetes_logs);
         Command::new("cp")
-            .arg("k8s/kubernetes/server/bin/kube-proxy")
-            .arg("k8s/kubernetes/server/bin/kubelet")
+            .arg(format!("k8s/{}/kube-proxy", kubernetes.dir))
+            .arg(format!("k8s/{}/kubelet", kubernetes.dir))
             .arg("to_send/kubernetes/bin")
             .status()
             .expect("Error happened when trying to copy `kubernetes` executable to `/opt/kubernetes/bin`");
 
         Command::new("cp")
-            .arg("k8s/kubernetes/server/bin/kube-apiserver")
-            .arg("k8s/kubernetes/server/bin/kube-scheduler")
-            .arg("k8s/kubernetes/server/bin/kube-controller-manager")
-            .arg("k8s/kubernetes/server/bin/kube-proxy")
-            .arg("k8s/kubernetes/server/bin/kubelet")
+            .arg(format!("k8s/{}/kube-apiserver", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-scheduler", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-controller-manager", kubernetes.dir))
+            .arg(format!("k8s/{}/kube-proxy", kubernetes.dir))
+            .arg(format!("k8s/{}/kubelet", kubernetes.dir))
             .arg("/opt/kubernetes/bin")
             .status()
             .expect("Error happened when trying to copy `kubernetes` executable to `/opt/kubernetes/bin`");
         Command::new("cp")
-            .arg("k8s/kubernetes/server/bin/kubectl")
+            .arg(format!("k8s/{}/kubectl", kubernetes.dir))
             .arg("/usr/bin")
             .status()
             .expect("Error happened when trying to copy `kubernetes` executable to `/usr/bin`");
@@ -80,6 +83,24 @@ pub fn start(config: &Config) {
     } else {
         tracing::error!("kubernetes not downloaded, please try again");
     }
+
+    tracing::info!("Start downloading CNI plugins...");
+    let cni = Artifact::cni(config, Arch::local());
+    artifact::download(
+        &cni.url,
+        &PathBuf::from(format!("k8s/{}", cni.file)),
+        &cni.expected_sha256(),
+    );
+    let cni_path = PathBuf::from("/opt/cni/bin");
+    check_dir_exist_or_create(cni_path);
+    Command::new("tar")
+        .arg("-zxf")
+        .arg(format!("k8s/{}", cni.file))
+        .arg("--directory")
+        .arg("/opt/cni/bin")
+        .status()
+        .expect("Error happened when trying to untar CNI plugins");
+    tracing::info!("CNI plugins installed");
 }
 
 fn check_dir_exist_or_create(path: PathBuf) {
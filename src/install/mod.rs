@@ -1,4 +1,5 @@
 pub mod cfssl;
+pub mod containerd;
 pub mod docker;
 pub mod etcd;
 pub mod kubernetes;
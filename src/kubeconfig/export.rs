@@ -0,0 +1,36 @@
+use crate::config::Config;
+use crate::kubeconfig;
+use crate::workdir;
+use std::path::PathBuf;
+
+/// Write the admin kubeconfig to `output`, or merge it into `merge` under
+/// `context_name`.
+pub fn start(config: &Config, output: PathBuf, merge: Option<PathBuf>, context_name: Option<&str>) {
+    let mut admin = kubeconfig::admin(config);
+    // Generic names like `default` would clobber those of other clusters.
+    match (context_name, &merge) {
+        (Some(context_name), _) => admin = admin.renamed(context_name),
+        (None, Some(_)) => admin = admin.renamed(workdir::cluster_name()),
+        (None, None) => {}
+    }
+    let server = &admin.clusters[0].cluster.server;
+    match merge {
+        Some(merge) => {
+            admin.merge_into(&merge);
+            tracing::info!(
+                "Context `{}` ({}) merged into {} and made current",
+                admin.current_context,
+                server,
+                merge.display()
+            );
+        }
+        None => {
+            admin.write(&output);
+            tracing::info!(
+                "Admin kubeconfig for {} written to {}",
+                server,
+                output.display()
+            );
+        }
+    }
+}
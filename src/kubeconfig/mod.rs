@@ -0,0 +1,262 @@
+pub mod export;
+pub mod user;
+
+use crate::config::Config;
+use crate::networking;
+use crate::workdir;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Kubeconfig file with every certificate embedded, so it stands on its own.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Kubeconfig {
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    pub kind: String,
+    pub clusters: Vec<NamedCluster>,
+    pub contexts: Vec<NamedContext>,
+    #[serde(rename = "current-context")]
+    pub current_context: String,
+    #[serde(default)]
+    pub preferences: serde_yaml::Mapping,
+    pub users: Vec<NamedUser>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NamedCluster {
+    pub name: String,
+    pub cluster: Cluster,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cluster {
+    pub server: String,
+    #[serde(rename = "certificate-authority-data")]
+    pub certificate_authority_data: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NamedContext {
+    pub name: String,
+    pub context: Context,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Context {
+    pub cluster: String,
+    pub user: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NamedUser {
+    pub name: String,
+    pub user: User,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct User {
+    #[serde(
+        rename = "client-certificate-data",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub client_certificate_data: Option<String>,
+    #[serde(rename = "client-key-data", skip_serializing_if = "Option::is_none")]
+    pub client_key_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl User {
+    /// Client certificate authentication, from PEM files.
+    pub fn certificate(cert: impl AsRef<Path>, key: impl AsRef<Path>) -> User {
+        User {
+            client_certificate_data: Some(embed(cert)),
+            client_key_data: Some(embed(key)),
+            token: None,
+        }
+    }
+
+    /// Bearer token authentication, such as the kubelet bootstrap token.
+    pub fn token(token: &str) -> User {
+        User {
+            token: Some(token.to_owned()),
+            ..User::default()
+        }
+    }
+}
+
+impl Kubeconfig {
+    /// Single cluster `kubernetes` reached at `server`, whose CA is the PEM
+    /// file `ca`, with `user` as the `default` context.
+    pub fn new(server: &str, ca: impl AsRef<Path>, user_name: &str, user: User) -> Kubeconfig {
+        Kubeconfig {
+            api_version: "v1".to_owned(),
+            kind: "Config".to_owned(),
+            clusters: vec![NamedCluster {
+                name: "kubernetes".to_owned(),
+                cluster: Cluster {
+                    server: server.to_owned(),
+                    certificate_authority_data: embed(ca),
+                },
+            }],
+            contexts: vec![NamedContext {
+                name: "default".to_owned(),
+                context: Context {
+                    cluster: "kubernetes".to_owned(),
+                    user: user_name.to_owned(),
+                },
+            }],
+            current_context: "default".to_owned(),
+            preferences: serde_yaml::Mapping::new(),
+            users: vec![NamedUser {
+                name: user_name.to_owned(),
+                user,
+            }],
+        }
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("Error happened when trying to serialize kubeconfig")
+    }
+
+    /// Write to `path`, readable by root only.
+    pub fn write(&self, path: impl AsRef<Path>) {
+        write_private(path.as_ref(), &self.to_yaml());
+    }
+
+    /// Rename the cluster, user and context after `name`, so they do not
+    /// clash with other clusters once merged.
+    pub fn renamed(mut self, name: &str) -> Kubeconfig {
+        for cluster in &mut self.clusters {
+            cluster.name = name.to_owned();
+        }
+        for user in &mut self.users {
+            user.name = format!("{}-{}", name, user.name);
+        }
+        for context in &mut self.contexts {
+            context.name = name.to_owned();
+            context.context.cluster = name.to_owned();
+            context.context.user = format!("{}-{}", name, context.context.user);
+        }
+        self.current_context = name.to_owned();
+        self
+    }
+
+    /// Add to the kubeconfig at `path`, replacing entries of the same names
+    /// and switching to this context. Whatever else is there is kept as is.
+    pub fn merge_into(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let mut merged = match fs::read_to_string(path) {
+            Ok(content) if !content.trim().is_empty() => {
+                serde_yaml::from_str::<serde_yaml::Value>(&content)
+                    .unwrap_or_else(|_| panic!("`{}` is not a valid kubeconfig", path.display()))
+            }
+            _ => serde_yaml::to_value(Kubeconfig {
+                clusters: Vec::new(),
+                contexts: Vec::new(),
+                users: Vec::new(),
+                ..self.clone()
+            })
+            .expect("Error happened when trying to serialize kubeconfig"),
+        };
+        let ours =
+            serde_yaml::to_value(self).expect("Error happened when trying to serialize kubeconfig");
+        let Some(merged_map) = merged.as_mapping_mut() else {
+            panic!("`{}` is not a valid kubeconfig", path.display());
+        };
+        for key in ["clusters", "contexts", "users"] {
+            let entries = merged_map
+                .entry(key.into())
+                .or_insert_with(|| serde_yaml::Value::Sequence(Vec::new()));
+            if entries.is_null() {
+                *entries = serde_yaml::Value::Sequence(Vec::new());
+            }
+            let Some(entries) = entries.as_sequence_mut() else {
+                panic!("`{}` of `{}` is not a list", key, path.display());
+            };
+            for entry in ours[key].as_sequence().into_iter().flatten() {
+                entries.retain(|existing| existing["name"] != entry["name"]);
+                entries.push(entry.clone());
+            }
+        }
+        merged_map.insert(
+            "current-context".into(),
+            self.current_context.as_str().into(),
+        );
+
+        let yaml = serde_yaml::to_string(&merged)
+            .expect("Error happened when trying to serialize kubeconfig");
+        write_private(path, &yaml);
+    }
+
+    /// Write to `to_send/{ip}/{file}` and upload it to `remote` on that node.
+    pub fn upload(&self, ip: &str, file: &str, remote: &str) {
+        let local = format!("to_send/{}/{}", ip, file);
+        self.write(&local);
+        let status = Command::new("scp")
+            .arg(&local)
+            .arg(format!("root@{}:{}", networking::host(ip), remote))
+            .status()
+            .expect("Error happened when trying to send files to other nodes");
+        if !status.success() {
+            panic!("Failed to upload `{}` to {}", remote, ip);
+        }
+    }
+}
+
+/// Admin kubeconfig kept on the deploying machine, next to the cluster CA.
+pub fn admin_path() -> PathBuf {
+    workdir::pki("k8s").join("admin.kubeconfig")
+}
+
+/// Cluster admin kubeconfig built from the certificates on this machine,
+/// reaching the control plane endpoint (or the first master).
+pub fn admin(config: &Config) -> Kubeconfig {
+    let pki = workdir::pki("k8s");
+    if !pki.join("admin.pem").is_file() {
+        panic!(
+            "No admin certificate under `{}`, please deploy the cluster from this machine first",
+            pki.display()
+        );
+    }
+    let (ip, _) = config.first_master();
+    Kubeconfig::new(
+        &networking::apiserver_url(config, ip),
+        pki.join("ca.pem"),
+        "cluster-admin",
+        User::certificate(pki.join("admin.pem"), pki.join("admin-key.pem")),
+    )
+}
+
+/// Write `content` to `path`, readable by root only as it holds a private key.
+fn write_private(path: &Path, content: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap_or_else(|_| {
+            panic!(
+                "Error happened when trying to create `{}`",
+                parent.display()
+            )
+        });
+    }
+    fs::write(path, content)
+        .unwrap_or_else(|_| panic!("Error happened when trying to write `{}`", path.display()));
+    fs::set_permissions(path, Permissions::from_mode(0o600)).unwrap_or_else(|_| {
+        panic!(
+            "Error happened when trying to restrict `{}`",
+            path.display()
+        )
+    });
+}
+
+/// Base64 of a PEM file, as kubeconfig `*-data` fields hold them.
+fn embed(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    let pem = fs::read(path)
+        .unwrap_or_else(|_| panic!("Error happened when trying to read `{}`", path.display()));
+    STANDARD.encode(pem)
+}
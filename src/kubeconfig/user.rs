@@ -0,0 +1,201 @@
+use crate::addon;
+use crate::config::Config;
+use crate::kubeconfig::{Kubeconfig, User};
+use crate::networking;
+use crate::workdir;
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+struct UserCsr {
+    CN: String,
+    key: Key,
+    names: Vec<Name>,
+}
+
+#[derive(Serialize, Debug)]
+struct Key {
+    algo: String,
+    size: i64,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+struct Name {
+    O: String,
+}
+
+/// Signing configuration of user certificates, valid for `ttl` and only
+/// usable by clients.
+fn signing_config(ttl: &str) -> serde_json::Value {
+    serde_json::json!({
+        "signing": {
+            "default": { "expiry": ttl },
+            "profiles": {
+                "user": {
+                    "expiry": ttl,
+                    "usages": ["signing", "key encipherment", "client auth"]
+                }
+            }
+        }
+    })
+}
+
+/// Sign a client certificate for `name` in `groups` and write a kubeconfig
+/// using it, bound to `clusterrole` in `namespace` (or cluster-wide) if given.
+pub fn start(
+    config: &Config,
+    name: &str,
+    groups: &[String],
+    ttl: &str,
+    clusterrole: Option<&str>,
+    namespace: Option<&str>,
+    output: Option<PathBuf>,
+) {
+    let name_pattern = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._@-]*$").unwrap();
+    if !name_pattern.is_match(name) {
+        panic!(
+            "`{}` is not a valid user name, use letters, digits, `.`, `_`, `@` and `-`",
+            name
+        );
+    }
+    // Go durations, which cfssl reads, have no days.
+    let ttl_pattern = Regex::new(r"^([0-9]+(h|m|s))+$").unwrap();
+    if !ttl_pattern.is_match(ttl) {
+        panic!(
+            "`{}` is not a valid ttl, expected something like `720h`",
+            ttl
+        );
+    }
+    if namespace.is_some() && clusterrole.is_none() {
+        panic!("`--namespace` only scopes the binding of `--clusterrole`");
+    }
+    // Both end up in a shell command run on the master.
+    let object_pattern = Regex::new(r"^[a-z0-9][a-z0-9:.-]*$").unwrap();
+    for (flag, value) in [("--clusterrole", clusterrole), ("--namespace", namespace)] {
+        if let Some(value) = value.filter(|value| !object_pattern.is_match(value)) {
+            panic!(
+                "`{}` is not a valid `{}`, use lowercase letters, digits, `:`, `.` and `-`",
+                value, flag
+            );
+        }
+    }
+    // Checked before signing, kubectl would only refuse it afterwards.
+    let binding = format!("rk8s-user-{}", name.to_lowercase().replace(['@', '_'], "-"));
+    let rfc1123 =
+        Regex::new(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?(\.[a-z0-9]([-a-z0-9]*[a-z0-9])?)*$").unwrap();
+    if clusterrole.is_some() && (binding.len() > 253 || !rfc1123.is_match(&binding)) {
+        panic!(
+            "`{}` derived from `{}` is not a valid binding name, please pick another user name",
+            binding, name
+        );
+    }
+    // Cluster CA, as left by the deploy and init phases.
+    let pki = workdir::pki("k8s");
+    let ca = pki.join("ca.pem");
+    let ca_key = pki.join("ca-key.pem");
+    if !ca.is_file() || !ca_key.is_file() {
+        panic!(
+            "No cluster CA under `{}`, please deploy the cluster from this machine first",
+            pki.display()
+        );
+    }
+
+    tracing::info!("Signing a certificate of {} valid for {}...", name, ttl);
+    let users = &pki.join("users");
+    fs::create_dir_all(users)
+        .unwrap_or_else(|_| panic!("Error happened when trying to create `{}`", users.display()));
+    let csr = UserCsr {
+        CN: name.to_owned(),
+        key: Key {
+            algo: config.kube_ctl_key_algo.to_owned(),
+            size: config.kube_ctl_key_size,
+        },
+        names: groups
+            .iter()
+            .map(|group| Name {
+                O: group.to_owned(),
+            })
+            .collect(),
+    };
+    let csr_path = users.join(format!("{}-csr.json", name));
+    fs::write(
+        &csr_path,
+        serde_json::to_string_pretty(&csr).expect("Error happened when trying to serialize csr"),
+    )
+    .expect("Error happened when trying to write user csr");
+    let config_path = users.join(format!("{}-ca-config.json", name));
+    fs::write(
+        &config_path,
+        serde_json::to_string_pretty(&signing_config(ttl))
+            .expect("Error happened when trying to serialize signing configuration"),
+    )
+    .expect("Error happened when trying to write signing configuration");
+
+    let bare = users.join(name);
+    let mut cfssl = Command::new("cfssl")
+        .arg("gencert")
+        .arg(format!("-ca={}", ca.display()))
+        .arg(format!("-ca-key={}", ca_key.display()))
+        .arg(format!("-config={}", config_path.display()))
+        .arg("-profile=user")
+        .arg(&csr_path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Error happened when trying to execute `cfssl` command");
+    let status = Command::new("cfssljson")
+        .arg("-bare")
+        .arg(&bare)
+        .stdin(Stdio::from(cfssl.stdout.take().unwrap()))
+        .status()
+        .expect("Error happened when trying to execute `cfssljson`");
+    let signed = cfssl
+        .wait()
+        .expect("Error happened when trying to execute `cfssl` command");
+    if !signed.success() || !status.success() {
+        panic!("Failed to sign a certificate of {}", name);
+    }
+    tracing::info!("Certificate of {} signed", name);
+
+    if let Some(clusterrole) = clusterrole {
+        let command = match namespace {
+            Some(namespace) => format!(
+                "kubectl create rolebinding {} --clusterrole={} --user={} -n {} \
+                --dry-run=client -o yaml | kubectl apply -f -",
+                binding, clusterrole, name, namespace
+            ),
+            None => format!(
+                "kubectl create clusterrolebinding {} --clusterrole={} --user={} \
+                --dry-run=client -o yaml | kubectl apply -f -",
+                binding, clusterrole, name
+            ),
+        };
+        tracing::info!("Binding {} to `{}`...", name, clusterrole);
+        if !addon::on_master(config, &command, false).success() {
+            panic!(
+                "Failed to bind {} to `{}`, the certificate is still under `{}`",
+                name,
+                clusterrole,
+                users.display()
+            );
+        }
+    }
+
+    let (ip, _) = config.first_master();
+    let kubeconfig = Kubeconfig::new(
+        &networking::apiserver_url(config, ip),
+        &ca,
+        name,
+        User::certificate(
+            users.join(format!("{}.pem", name)),
+            users.join(format!("{}-key.pem", name)),
+        ),
+    );
+    let output = output.unwrap_or_else(|| users.join(format!("{}.kubeconfig", name)));
+    kubeconfig.write(&output);
+    tracing::info!("Kubeconfig of {} written to {}", name, output.display());
+}
@@ -0,0 +1,148 @@
+use crate::config::{Config, LoadBalancer};
+use crate::networking;
+use crate::template;
+use std::net::IpAddr;
+
+/// Shell command installing `packages` with the package manager of the
+/// node, unless their commands are all there already.
+pub fn install_command(packages: &[&str]) -> String {
+    let present = packages
+        .iter()
+        .map(|package| format!("command -v {} >/dev/null", package))
+        .collect::<Vec<_>>()
+        .join(" && ");
+    let packages = packages.join(" ");
+    format!(
+        "{present} || \
+        if command -v dnf >/dev/null; then dnf install -y {packages}; \
+        elif command -v yum >/dev/null; then yum install -y {packages}; \
+        else apt-get update && apt-get install -y {packages}; fi"
+    )
+}
+
+fn vip(load_balancer: &LoadBalancer) -> IpAddr {
+    load_balancer
+        .vip
+        .parse()
+        .expect("`load_balancer.vip` is not a valid IP address")
+}
+
+/// Masters sharing the VIP, the first one holding it to begin with.
+pub fn masters(config: &Config) -> Vec<(&String, &String)> {
+    let mut masters: Vec<_> = config
+        .instance_hosts
+        .iter()
+        .filter(|(_, name)| name.contains("master"))
+        .collect();
+    masters.sort();
+    masters
+}
+
+/// HAProxy listening on the VIP port of every master.
+pub fn vip_bind(load_balancer: &LoadBalancer) -> String {
+    if vip(load_balancer).is_ipv6() {
+        format!(":::{} v4v6", load_balancer.port)
+    } else {
+        format!("*:{}", load_balancer.port)
+    }
+}
+
+/// HAProxy listening on the apiserver port of a worker, only for itself.
+pub fn node_local_bind(config: &Config) -> String {
+    format!(
+        "{}:{}",
+        networking::loopback(config),
+        config.networking.apiserver_port
+    )
+}
+
+/// HAProxy configuration spreading connections on `bind` over every
+/// apiserver.
+pub fn haproxy_cfg(config: &Config, bind: &str) -> String {
+    // HAProxy splits addresses on their last colon, IPv6 ones go bare.
+    let servers = masters(config)
+        .iter()
+        .map(|(ip, name)| {
+            format!(
+                "    server {} {}:{}",
+                name, ip, config.networking.apiserver_port
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    template::render(
+        include_str!("../templates/load-balancer/haproxy.cfg"),
+        &[("bind", bind), ("servers", &servers)],
+    )
+}
+
+pub fn keepalived_conf(
+    config: &Config,
+    load_balancer: &LoadBalancer,
+    current_ip: &str,
+    interface: &str,
+) -> String {
+    let masters = masters(config);
+    let (index, (_, name)) = masters
+        .iter()
+        .enumerate()
+        .find(|(_, (ip, _))| ip.as_str() == current_ip)
+        .expect("Keepalived only runs on masters");
+    // A failing check costs 20, which drops a master below every other one.
+    let priority = (150 - index).to_string();
+    let state = if index == 0 { "MASTER" } else { "BACKUP" };
+    template::render(
+        include_str!("../templates/load-balancer/keepalived.conf"),
+        &[
+            ("name", name),
+            ("state", state),
+            ("interface", interface),
+            (
+                "virtual_router_id",
+                &load_balancer.virtual_router_id.to_string(),
+            ),
+            ("priority", &priority),
+            ("vip", &load_balancer.vip),
+        ],
+    )
+}
+
+pub fn check_script(load_balancer: &LoadBalancer) -> String {
+    let address = if vip(load_balancer).is_ipv6() {
+        "[::1]"
+    } else {
+        "127.0.0.1"
+    };
+    template::render(
+        include_str!("../templates/load-balancer/check_apiserver.sh"),
+        &[
+            ("address", address),
+            ("port", &load_balancer.port.to_string()),
+        ],
+    )
+}
+
+/// Shell command printing the route to the VIP, such as
+/// `10.0.0.100 dev eth0 src 10.0.0.11 uid 0`.
+pub fn route_command(load_balancer: &LoadBalancer) -> String {
+    format!("ip -o route get {}", load_balancer.vip)
+}
+
+/// Interface out of `ip route get` output.
+pub fn route_interface(route: &str) -> Option<&str> {
+    let mut fields = route.split_whitespace();
+    fields.find(|field| *field == "dev")?;
+    fields.next()
+}
+
+/// Shell command waiting up to a minute for the apiserver to answer on the
+/// control plane endpoint.
+pub fn wait_command(config: &Config) -> String {
+    let (host, port) =
+        networking::control_plane_endpoint(config).expect("No control plane endpoint to wait for");
+    format!(
+        "for i in $(seq 30); do curl -sfk --max-time 2 -o /dev/null https://{}:{}/healthz && exit 0; sleep 2; done; exit 1",
+        networking::host(&host),
+        port
+    )
+}
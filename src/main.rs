@@ -1,14 +1,36 @@
+mod addon;
+mod arch;
+mod artifact;
+mod bundle;
+mod cni;
+mod component_config;
 mod config;
 mod deploy;
+mod dns;
+mod health;
 mod init;
 mod install;
 mod join;
+mod kubeconfig;
+mod load_balancer;
+mod networking;
+mod oci;
+mod proxy;
+mod registry;
 mod rk8s;
+mod runtime;
+mod status;
+mod template;
+mod version;
+mod workdir;
 
 use rk8s::run_command;
 
 fn main() {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, keeping stdout for reports such as `status --output json`.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
     tracing::info!("rk8s started");
     run_command();
 }
@@ -0,0 +1,719 @@
+use crate::config::Config;
+use crate::version::KubeVersion;
+use std::fmt;
+use std::net::IpAddr;
+
+/// IPv4 or IPv6 network in CIDR notation, such as `10.0.0.0/24`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn parse(cidr: &str) -> Option<Cidr> {
+        let (addr, prefix) = cidr.trim().split_once('/')?;
+        let addr: IpAddr = addr.parse().ok()?;
+        let prefix: u8 = prefix.parse().ok()?;
+        if prefix > Cidr::width(addr) {
+            return None;
+        }
+        let cidr = Cidr { addr, prefix };
+        // Host bits set usually mean a typo.
+        (Cidr::value(addr) == cidr.network()).then_some(cidr)
+    }
+
+    fn width(addr: IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        let host_bits = (Cidr::width(self.addr) - self.prefix) as u32;
+        match self.addr {
+            IpAddr::V4(_) => u32::MAX.checked_shl(host_bits).unwrap_or(0) as u128,
+            IpAddr::V6(_) => u128::MAX.checked_shl(host_bits).unwrap_or(0),
+        }
+    }
+
+    fn value(addr: IpAddr) -> u128 {
+        match addr {
+            IpAddr::V4(addr) => u32::from(addr) as u128,
+            IpAddr::V6(addr) => u128::from(addr),
+        }
+    }
+
+    // Address as an integer, masked with the prefix.
+    fn bits(&self, addr: IpAddr) -> u128 {
+        Cidr::value(addr) & self.mask()
+    }
+
+    fn network(&self) -> u128 {
+        self.bits(self.addr)
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        self.addr.is_ipv6()
+    }
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv6() == self.is_ipv6() && self.bits(addr) == self.network()
+    }
+
+    pub fn overlaps(&self, other: &Cidr) -> bool {
+        if self.is_ipv6() != other.is_ipv6() {
+            return false;
+        }
+        let wider = if self.prefix <= other.prefix {
+            self
+        } else {
+            other
+        };
+        wider.contains(self.addr) && wider.contains(other.addr)
+    }
+
+    /// The `n`th address of the network, the network address being the 0th.
+    pub fn nth(&self, n: u32) -> IpAddr {
+        let addr = self.network() + n as u128;
+        match self.addr {
+            IpAddr::V4(_) => IpAddr::from((addr as u32).to_be_bytes()),
+            IpAddr::V6(_) => IpAddr::from(addr.to_be_bytes()),
+        }
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.nth(0), self.prefix)
+    }
+}
+
+/// Ranges of a single or dual-stack setting, such as
+/// `10.244.0.0/16,fd00:10:244::/56`, the first one being the primary.
+pub fn parse_cidrs(field: &str, value: &str) -> Vec<Cidr> {
+    let cidrs: Vec<Cidr> = value
+        .split(',')
+        .map(|cidr| {
+            Cidr::parse(cidr).unwrap_or_else(|| {
+                panic!(
+                    "`{}` is not a valid CIDR, such as `10.0.0.0/24` or `fd00::/108`: {}",
+                    field, cidr
+                )
+            })
+        })
+        .collect();
+    let dual_stack = cidrs.len() == 2 && cidrs[0].is_ipv6() != cidrs[1].is_ipv6();
+    if cidrs.len() > 2 || (cidrs.len() == 2 && !dual_stack) {
+        panic!(
+            "`{}` takes one range, or one IPv4 and one IPv6 range for dual-stack: {}",
+            field, value
+        );
+    }
+    cidrs
+}
+
+/// Range of `value` in the given family, if any.
+pub fn cidr_of_family(value: &str, ipv6: bool) -> Option<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .find(|cidr| Cidr::parse(cidr).is_some_and(|cidr| cidr.is_ipv6() == ipv6))
+        .map(str::to_owned)
+}
+
+/// IP families pods get addresses in, as (IPv4, IPv6).
+pub fn pod_families(config: &Config) -> (bool, bool) {
+    let pod = &config.networking.pod_cidr;
+    (
+        cidr_of_family(pod, false).is_some(),
+        cidr_of_family(pod, true).is_some(),
+    )
+}
+
+/// Content of `/etc/sysctl.d/k8s.conf`, forwarding being turned on for every
+/// IP family pods use.
+pub fn sysctl_conf(config: &Config) -> String {
+    let (ipv4, ipv6) = pod_families(config);
+    let mut content =
+        "net.bridge.bridge-nf-call-ip6tables = 1\nnet.bridge.bridge-nf-call-iptables = 1\n"
+            .to_owned();
+    if ipv4 {
+        content.push_str("net.ipv4.ip_forward = 1\n");
+    }
+    if ipv6 {
+        // Forwarding makes the kernel ignore router advertisements otherwise.
+        content.push_str("net.ipv6.conf.all.forwarding = 1\nnet.ipv6.conf.all.accept_ra = 2\n");
+    }
+    content
+}
+
+/// ClusterIP of the `kubernetes` service, which the apiserver certificate
+/// has to be valid for.
+pub fn api_service_ip(config: &Config) -> IpAddr {
+    parse_cidrs("service_cidr", &config.networking.service_cidr)[0].nth(1)
+}
+
+/// Host part of a URL or scp destination, IPv6 addresses being bracketed.
+pub fn host(ip: &str) -> String {
+    if ip.contains(':') {
+        format!("[{}]", ip)
+    } else {
+        ip.to_owned()
+    }
+}
+
+/// Host and port of `control_plane_endpoint`, falling back to the load
+/// balancer VIP.
+pub fn control_plane_endpoint(config: &Config) -> Option<(String, u16)> {
+    // A bare host is served on the load balancer port if there is one.
+    let default_port = match &config.load_balancer {
+        Some(load_balancer) => load_balancer.port,
+        None => config.networking.apiserver_port,
+    };
+    let Some(endpoint) = &config.control_plane_endpoint else {
+        return config
+            .load_balancer
+            .as_ref()
+            .map(|load_balancer| (load_balancer.vip.to_owned(), load_balancer.port));
+    };
+    let (host, port) = split_endpoint(endpoint.trim()).unwrap_or_else(|| {
+        panic!(
+            "`control_plane_endpoint` is not a valid `host` or `host:port`: {}",
+            endpoint
+        )
+    });
+    Some((host.to_owned(), port.unwrap_or(default_port)))
+}
+
+fn split_endpoint(endpoint: &str) -> Option<(&str, Option<u16>)> {
+    // IPv6 addresses need brackets when followed by a port.
+    let (host, port) = if let Some(rest) = endpoint.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        match port {
+            "" => (host, None),
+            port => (host, Some(port.strip_prefix(':')?)),
+        }
+    } else if endpoint.parse::<IpAddr>().is_ok() {
+        (endpoint, None)
+    } else {
+        match endpoint.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (endpoint, None),
+        }
+    };
+    if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == '/') {
+        return None;
+    }
+    match port {
+        Some(port) => Some((host, Some(port.parse().ok().filter(|port| *port != 0)?))),
+        None => Some((host, None)),
+    }
+}
+
+/// Names and addresses the apiserver is reached through besides the nodes,
+/// which its certificate has to cover.
+pub fn endpoint_hosts(config: &Config) -> Vec<String> {
+    let mut hosts = Vec::new();
+    if let Some((host, _)) = control_plane_endpoint(config) {
+        hosts.push(host);
+    }
+    if let Some(load_balancer) = &config.load_balancer {
+        if !hosts.contains(&load_balancer.vip) {
+            hosts.push(load_balancer.vip.to_owned());
+        }
+    }
+    // `127.0.0.1` is always there.
+    if config.node_local_load_balancer && loopback(config) == "::1" {
+        hosts.push("::1".to_owned());
+    }
+    hosts
+}
+
+/// Loopback address workers' HAProxy listens on, in the family of masters.
+pub fn loopback(config: &Config) -> &'static str {
+    if config.first_master().0.contains(':') {
+        "::1"
+    } else {
+        "127.0.0.1"
+    }
+}
+
+/// Address of the apiserver as seen from a master, the control plane
+/// endpoint when there is one.
+pub fn apiserver_url(config: &Config, ip: &str) -> String {
+    match control_plane_endpoint(config) {
+        Some((endpoint, port)) => format!("https://{}:{}", host(&endpoint), port),
+        None => format!("https://{}:{}", host(ip), config.networking.apiserver_port),
+    }
+}
+
+/// Address of the apiserver as seen from a worker, which has none of its own.
+pub fn worker_apiserver_url(config: &Config) -> String {
+    if config.node_local_load_balancer {
+        let port = config.networking.apiserver_port;
+        return format!("https://{}:{}", host(loopback(config)), port);
+    }
+    apiserver_url(config, config.first_master().0)
+}
+
+/// Address of the apiserver as seen from any node.
+pub fn node_apiserver_url(config: &Config, ip: &str, name: &str) -> String {
+    if name.contains("master") {
+        apiserver_url(config, ip)
+    } else {
+        worker_apiserver_url(config)
+    }
+}
+
+/// Check the `networking` section once, before anything is generated from it.
+pub fn validate(config: &Config) {
+    let networking = &config.networking;
+    let service = parse_cidrs("service_cidr", &networking.service_cidr);
+    let pod = parse_cidrs("pod_cidr", &networking.pod_cidr);
+    for service_cidr in &service {
+        for pod_cidr in &pod {
+            if service_cidr.overlaps(pod_cidr) {
+                panic!(
+                    "`service_cidr` {} and `pod_cidr` {} overlap",
+                    service_cidr, pod_cidr
+                );
+            }
+        }
+    }
+    // Services have to be reachable from every pod family.
+    if service.len() != pod.len() || service[0].is_ipv6() != pod[0].is_ipv6() {
+        panic!(
+            "`service_cidr` {} and `pod_cidr` {} need the same IP families, in the same order",
+            networking.service_cidr, networking.pod_cidr
+        );
+    }
+    if service.len() == 2 && !KubeVersion::from(config).has_dual_stack() {
+        panic!("Dual-stack `service_cidr` and `pod_cidr` need kubernetes v1.21 or later");
+    }
+    for ip in config.instance_hosts.keys() {
+        let addr: IpAddr = ip
+            .parse()
+            .unwrap_or_else(|_| panic!("Node address {} is not a valid IP address", ip));
+        for (field, cidr) in service
+            .iter()
+            .map(|cidr| ("service_cidr", cidr))
+            .chain(pod.iter().map(|cidr| ("pod_cidr", cidr)))
+        {
+            if cidr.contains(addr) {
+                panic!("`{}` {} overlaps node {}", field, cidr, ip);
+            }
+        }
+        if !service.iter().any(|cidr| cidr.is_ipv6() == addr.is_ipv6()) {
+            panic!(
+                "Node {} has no `service_cidr` range of its IP family: {}",
+                ip, networking.service_cidr
+            );
+        }
+    }
+
+    let dns: IpAddr = networking
+        .dns_service_ip
+        .parse()
+        .unwrap_or_else(|_| panic!("`dns_service_ip` is not a valid IP address"));
+    let Some(dns_range) = service.iter().find(|cidr| cidr.contains(dns)) else {
+        panic!(
+            "`dns_service_ip` {} is outside of `service_cidr` {}",
+            dns, networking.service_cidr
+        );
+    };
+    if dns == dns_range.nth(0) || dns == dns_range.nth(1) {
+        panic!(
+            "`dns_service_ip` {} is reserved, {} being the network address and {} the apiserver",
+            dns,
+            dns_range.nth(0),
+            dns_range.nth(1)
+        );
+    }
+
+    if networking.cluster_domain.is_empty()
+        || networking.cluster_domain.starts_with('.')
+        || networking.cluster_domain.ends_with('.')
+    {
+        panic!(
+            "`cluster_domain` is not a valid domain: `{}`",
+            networking.cluster_domain
+        );
+    }
+
+    let (low, high) = networking
+        .node_port_range
+        .split_once('-')
+        .and_then(|(low, high)| {
+            Some((
+                low.trim().parse::<u16>().ok()?,
+                high.trim().parse::<u16>().ok()?,
+            ))
+        })
+        .filter(|(low, high)| low < high)
+        .unwrap_or_else(|| {
+            panic!(
+                "`node_port_range` is not a valid port range, such as `30000-32767`: {}",
+                networking.node_port_range
+            )
+        });
+    if networking.apiserver_port == 0 {
+        panic!("`apiserver_port` can not be 0");
+    }
+    if (low..=high).contains(&networking.apiserver_port) {
+        panic!(
+            "`apiserver_port` {} is inside `node_port_range` {}",
+            networking.apiserver_port, networking.node_port_range
+        );
+    }
+
+    control_plane_endpoint(config);
+    if let Some(load_balancer) = &config.load_balancer {
+        let vip: IpAddr = load_balancer
+            .vip
+            .parse()
+            .unwrap_or_else(|_| panic!("`load_balancer.vip` is not a valid IP address"));
+        if config.instance_hosts.contains_key(&load_balancer.vip) {
+            panic!("`load_balancer.vip` {} is already a node address", vip);
+        }
+        for cidr in service.iter().chain(pod.iter()) {
+            if cidr.contains(vip) {
+                panic!("`load_balancer.vip` {} is inside {}", vip, cidr);
+            }
+        }
+        if load_balancer.port == 0 || load_balancer.port == networking.apiserver_port {
+            panic!(
+                "`load_balancer.port` {} has to differ from `apiserver_port`, \
+                HAProxy listening next to the apiserver",
+                load_balancer.port
+            );
+        }
+        if (low..=high).contains(&load_balancer.port) {
+            panic!(
+                "`load_balancer.port` {} is inside `node_port_range` {}",
+                load_balancer.port, networking.node_port_range
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use std::collections::HashMap;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn parse_cidr() {
+        let cases = [
+            ("10.0.0.0/24", Some("10.0.0.0/24")),
+            (" 10.244.0.0/16 ", Some("10.244.0.0/16")),
+            ("0.0.0.0/0", Some("0.0.0.0/0")),
+            ("10.0.0.7/32", Some("10.0.0.7/32")),
+            // Host bits set.
+            ("10.0.0.1/24", None),
+            ("10.0.0.0/33", None),
+            ("10.0.0.0", None),
+            ("10.0.0.0/x", None),
+            ("example/24", None),
+        ];
+        for (cidr, expected) in cases {
+            let parsed = Cidr::parse(cidr).map(|cidr| cidr.to_string());
+            assert_eq!(parsed.as_deref(), expected, "{}", cidr);
+        }
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let cases = [
+            ("10.0.0.0/24", "10.0.0.0/16", true),
+            ("10.0.0.0/16", "10.0.128.0/24", true),
+            ("10.0.0.0/24", "10.0.1.0/24", false),
+            ("10.0.0.0/24", "10.244.0.0/16", false),
+            ("0.0.0.0/0", "10.244.0.0/16", true),
+        ];
+        for (a, b, expected) in cases {
+            let (a, b) = (Cidr::parse(a).unwrap(), Cidr::parse(b).unwrap());
+            assert_eq!(a.overlaps(&b), expected, "{} {}", a, b);
+            assert_eq!(b.overlaps(&a), expected, "{} {}", b, a);
+        }
+    }
+
+    #[test]
+    fn nth_address() {
+        let cidr = Cidr::parse("10.0.0.0/24").unwrap();
+        assert_eq!(cidr.nth(0).to_string(), "10.0.0.0");
+        assert_eq!(cidr.nth(10).to_string(), "10.0.0.10");
+    }
+
+    /// Message `validate` panics with once `edit` is applied to a single
+    /// master configuration.
+    fn validate_error(edit: impl FnOnce(&mut Config)) -> Option<String> {
+        let mut config = config::template();
+        config.instance_hosts = HashMap::from([("192.168.1.10".to_owned(), "master01".to_owned())]);
+        edit(&mut config);
+        panic::catch_unwind(AssertUnwindSafe(|| validate(&config)))
+            .err()
+            .map(|error| match error.downcast::<String>() {
+                Ok(message) => *message,
+                // Messages without arguments are not formatted.
+                Err(error) => error.downcast::<&str>().unwrap().to_string(),
+            })
+    }
+
+    // Name of the case, change to the configuration and expected error.
+    type Case<'a> = (&'a str, Box<dyn FnOnce(&mut Config)>, Option<&'a str>);
+
+    fn assert_validation(cases: Vec<Case>) {
+        for (case, edit, expected) in cases {
+            let error = validate_error(edit);
+            match (&error, expected) {
+                (None, None) => {}
+                (Some(error), Some(expected)) if error.contains(expected) => {}
+                _ => panic!("{}: expected {:?}, got {:?}", case, expected, error),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_networking() {
+        assert_validation(vec![
+            ("defaults", Box::new(|_| {}), None),
+            (
+                "overlapping ranges",
+                Box::new(|config| config.networking.pod_cidr = "10.0.0.0/16".to_owned()),
+                Some("overlap"),
+            ),
+            (
+                "range over a node",
+                Box::new(|config| config.networking.pod_cidr = "192.168.0.0/16".to_owned()),
+                Some("overlaps node"),
+            ),
+            (
+                "host bits set",
+                Box::new(|config| config.networking.service_cidr = "10.0.0.1/24".to_owned()),
+                Some("not a valid"),
+            ),
+            (
+                "dns outside of services",
+                Box::new(|config| config.networking.dns_service_ip = "10.0.1.2".to_owned()),
+                Some("outside of"),
+            ),
+            (
+                "dns on the apiserver address",
+                Box::new(|config| config.networking.dns_service_ip = "10.0.0.1".to_owned()),
+                Some("is reserved"),
+            ),
+            (
+                "dotted domain",
+                Box::new(|config| config.networking.cluster_domain = "cluster.local.".to_owned()),
+                Some("`cluster_domain`"),
+            ),
+            (
+                "reversed node ports",
+                Box::new(|config| config.networking.node_port_range = "32767-30000".to_owned()),
+                Some("`node_port_range`"),
+            ),
+            (
+                "apiserver on a node port",
+                Box::new(|config| config.networking.apiserver_port = 30443),
+                Some("inside `node_port_range`"),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn parse_ipv6_cidr() {
+        let cases = [
+            ("fd00:10:244::/56", Some("fd00:10:244::/56")),
+            ("fd00::/128", Some("fd00::/128")),
+            ("::/0", Some("::/0")),
+            // Host bits set.
+            ("fd00::1/64", None),
+            ("fd00::/129", None),
+            ("fd00::", None),
+        ];
+        for (cidr, expected) in cases {
+            let parsed = Cidr::parse(cidr).map(|cidr| cidr.to_string());
+            assert_eq!(parsed.as_deref(), expected, "{}", cidr);
+        }
+    }
+
+    #[test]
+    fn overlapping_ipv6_ranges() {
+        let cases = [
+            ("fd00::/108", "fd00::/64", true),
+            ("fd00:10:96::/108", "fd00:10:244::/56", false),
+            // Families never overlap.
+            ("0.0.0.0/0", "::/0", false),
+        ];
+        for (a, b, expected) in cases {
+            let (a, b) = (Cidr::parse(a).unwrap(), Cidr::parse(b).unwrap());
+            assert_eq!(a.overlaps(&b), expected, "{} {}", a, b);
+            assert_eq!(b.overlaps(&a), expected, "{} {}", b, a);
+        }
+    }
+
+    #[test]
+    fn nth_ipv6_address() {
+        assert_eq!(
+            Cidr::parse("fd00:10:96::/108").unwrap().nth(10).to_string(),
+            "fd00:10:96::a"
+        );
+    }
+
+    #[test]
+    fn dual_stack_ranges() {
+        let cidrs = parse_cidrs("pod_cidr", "10.244.0.0/16,fd00:10:244::/56");
+        assert_eq!(cidrs.len(), 2);
+        assert!(!cidrs[0].is_ipv6() && cidrs[1].is_ipv6());
+        let value = "fd00:10:244::/56, 10.244.0.0/16";
+        assert_eq!(
+            cidr_of_family(value, false).as_deref(),
+            Some("10.244.0.0/16")
+        );
+        assert_eq!(
+            cidr_of_family(value, true).as_deref(),
+            Some("fd00:10:244::/56")
+        );
+        assert_eq!(cidr_of_family("10.244.0.0/16", true), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "one IPv4 and one IPv6 range")]
+    fn same_family_twice() {
+        parse_cidrs("pod_cidr", "10.244.0.0/16,10.245.0.0/16");
+    }
+
+    #[test]
+    fn bracket_ipv6_hosts() {
+        assert_eq!(host("192.168.1.10"), "192.168.1.10");
+        assert_eq!(host("fd00::10"), "[fd00::10]");
+    }
+
+    #[test]
+    fn split_endpoints() {
+        let cases = [
+            ("k8s.example.com", Some(("k8s.example.com", None))),
+            (
+                "k8s.example.com:8443",
+                Some(("k8s.example.com", Some(8443))),
+            ),
+            ("192.168.1.100:6443", Some(("192.168.1.100", Some(6443)))),
+            ("fd00::100", Some(("fd00::100", None))),
+            ("[fd00::100]", Some(("fd00::100", None))),
+            ("[fd00::100]:8443", Some(("fd00::100", Some(8443)))),
+            ("[fd00::100]8443", None),
+            ("k8s.example.com:0", None),
+            ("k8s.example.com:http", None),
+            (":6443", None),
+        ];
+        for (endpoint, expected) in cases {
+            assert_eq!(split_endpoint(endpoint), expected, "{}", endpoint);
+        }
+    }
+
+    /// Sets the ranges, the CoreDNS address and the only node of a case.
+    fn ranges(
+        version: &'static str,
+        service_cidr: &'static str,
+        pod_cidr: &'static str,
+        dns_service_ip: &'static str,
+        node: &'static str,
+    ) -> Box<dyn FnOnce(&mut Config)> {
+        Box::new(move |config| {
+            config.kubernetes_version = version.to_owned();
+            config.networking.service_cidr = service_cidr.to_owned();
+            config.networking.pod_cidr = pod_cidr.to_owned();
+            config.networking.dns_service_ip = dns_service_ip.to_owned();
+            config.instance_hosts = HashMap::from([(node.to_owned(), "master01".to_owned())]);
+        })
+    }
+
+    #[test]
+    fn validate_dual_stack() {
+        let dual_services = "10.0.0.0/24,fd00:10:96::/108";
+        let dual_pods = "10.244.0.0/16,fd00:10:244::/56";
+        assert_validation(vec![
+            (
+                "IPv6 only",
+                ranges(
+                    "v1.20.15",
+                    "fd00:10:96::/108",
+                    "fd00:10:244::/56",
+                    "fd00:10:96::a",
+                    "fd00::10",
+                ),
+                None,
+            ),
+            (
+                "dual-stack",
+                ranges(
+                    "v1.28.4",
+                    dual_services,
+                    dual_pods,
+                    "10.0.0.2",
+                    "192.168.1.10",
+                ),
+                None,
+            ),
+            (
+                "dual-stack before v1.21",
+                ranges(
+                    "v1.20.15",
+                    dual_services,
+                    dual_pods,
+                    "10.0.0.2",
+                    "192.168.1.10",
+                ),
+                Some("need kubernetes v1.21"),
+            ),
+            (
+                "reversed families",
+                ranges(
+                    "v1.28.4",
+                    dual_services,
+                    "fd00:10:244::/56,10.244.0.0/16",
+                    "10.0.0.2",
+                    "192.168.1.10",
+                ),
+                Some("same IP families, in the same order"),
+            ),
+            (
+                "single service range",
+                ranges(
+                    "v1.28.4",
+                    "10.0.0.0/24",
+                    dual_pods,
+                    "10.0.0.2",
+                    "192.168.1.10",
+                ),
+                Some("same IP families"),
+            ),
+            (
+                "overlapping IPv6 ranges",
+                ranges(
+                    "v1.28.4",
+                    "10.0.0.0/24,fd00:10::/108",
+                    "10.244.0.0/16,fd00:10::/56",
+                    "10.0.0.2",
+                    "192.168.1.10",
+                ),
+                Some("overlap"),
+            ),
+            (
+                "IPv6 node without an IPv6 service range",
+                ranges(
+                    "v1.28.4",
+                    "10.0.0.0/24",
+                    "10.244.0.0/16",
+                    "10.0.0.2",
+                    "fd00::10",
+                ),
+                Some("no `service_cidr` range"),
+            ),
+        ]);
+    }
+}
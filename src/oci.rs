@@ -0,0 +1,79 @@
+use crate::arch::Arch;
+use crate::artifact::Artifact;
+use crate::config::Config;
+use std::collections::HashMap;
+use std::fmt;
+
+// Image the post-install smoke test runs `true` in.
+pub const SMOKE_TEST_IMAGE: &str = "docker.io/library/busybox:1.36";
+
+/// Low level runtime the container runtime spawns containers with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OciRuntime {
+    Runc,
+    Youki,
+    Crun,
+}
+
+impl OciRuntime {
+    pub fn parse(name: &str) -> Option<OciRuntime> {
+        match name.trim() {
+            "runc" => Some(OciRuntime::Runc),
+            "youki" => Some(OciRuntime::Youki),
+            "crun" => Some(OciRuntime::Crun),
+            _ => None,
+        }
+    }
+
+    /// Default OCI runtime of a node, `oci_runtimes` overriding `oci_runtime`.
+    pub fn of(config: &Config, ip: &String) -> OciRuntime {
+        let name = config.oci_runtimes.get(ip).unwrap_or(&config.oci_runtime);
+        OciRuntime::parse(name).unwrap_or_else(|| {
+            panic!(
+                "OCI runtime `{}` set for {} is not supported, expected `runc`, `youki` or `crun`",
+                name, ip
+            )
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OciRuntime::Runc => "runc",
+            OciRuntime::Youki => "youki",
+            OciRuntime::Crun => "crun",
+        }
+    }
+
+    /// Where rk8s installs the binary, runc being shipped by every container
+    /// runtime already.
+    pub fn path(&self) -> Option<&'static str> {
+        match self {
+            OciRuntime::Runc => None,
+            OciRuntime::Youki => Some("/usr/bin/youki"),
+            OciRuntime::Crun => Some("/usr/bin/crun"),
+        }
+    }
+
+    pub fn artifact(&self, config: &Config, arch: Arch) -> Option<Artifact> {
+        match self {
+            OciRuntime::Runc => None,
+            OciRuntime::Youki => Some(Artifact::youki(config, arch)),
+            OciRuntime::Crun => Some(Artifact::crun(config, arch)),
+        }
+    }
+}
+
+impl fmt::Display for OciRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Default OCI runtime of every node in `instance_hosts`.
+pub fn of_hosts(config: &Config) -> HashMap<String, OciRuntime> {
+    config
+        .instance_hosts
+        .keys()
+        .map(|ip| (ip.to_owned(), OciRuntime::of(config, ip)))
+        .collect()
+}
@@ -0,0 +1,100 @@
+use crate::config::Config;
+use crate::version::KubeVersion;
+use std::fmt;
+
+/// How kube-proxy implements services on every node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyMode {
+    Iptables,
+    Ipvs,
+    Nftables,
+}
+
+impl ProxyMode {
+    pub fn parse(name: &str) -> Option<ProxyMode> {
+        match name.trim() {
+            "iptables" => Some(ProxyMode::Iptables),
+            "ipvs" => Some(ProxyMode::Ipvs),
+            "nftables" => Some(ProxyMode::Nftables),
+            _ => None,
+        }
+    }
+
+    pub fn from(config: &Config) -> ProxyMode {
+        let mode = ProxyMode::parse(&config.proxy_mode).unwrap_or_else(|| {
+            panic!(
+                "Proxy mode `{}` is not supported, expected `iptables`, `ipvs` or `nftables`",
+                config.proxy_mode
+            )
+        });
+        let version = KubeVersion::from(config);
+        if mode == ProxyMode::Nftables && !version.has_nftables_proxy() {
+            panic!(
+                "kube-proxy supports `nftables` mode from v1.29 on, kubernetes {} is used",
+                version
+            );
+        }
+        mode
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyMode::Iptables => "iptables",
+            ProxyMode::Ipvs => "ipvs",
+            ProxyMode::Nftables => "nftables",
+        }
+    }
+
+    /// Kernel modules kube-proxy relies on in this mode.
+    pub fn modules(&self, config: &Config) -> Vec<String> {
+        match self {
+            ProxyMode::Iptables => Vec::new(),
+            ProxyMode::Ipvs => vec![
+                "ip_vs".to_owned(),
+                format!("ip_vs_{}", config.ipvs_scheduler),
+                "nf_conntrack".to_owned(),
+            ],
+            ProxyMode::Nftables => vec!["nf_tables".to_owned()],
+        }
+    }
+}
+
+impl fmt::Display for ProxyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Content of `/etc/modules-load.d/k8s.conf`.
+pub fn modules_load_conf(config: &Config) -> String {
+    let mut content = "br_netfilter\n".to_owned();
+    for module in ProxyMode::from(config).modules(config) {
+        content.push_str(&module);
+        content.push('\n');
+    }
+    content
+}
+
+/// Shell command loading the modules listed in `/etc/modules-load.d/k8s.conf`
+/// and failing unless every one of them, and the tools kube-proxy calls, is
+/// present.
+pub fn preflight_command(config: &Config) -> String {
+    let mode = ProxyMode::from(config);
+    let mut modules = vec!["br_netfilter".to_owned()];
+    modules.extend(mode.modules(config));
+    let mut command = format!(
+        "for module in {0}; do modprobe $module; \
+        if [ ! -d /sys/module/$module ]; then echo \"kernel module $module is missing\"; exit 1; fi; done",
+        modules.join(" ")
+    );
+    match mode {
+        ProxyMode::Iptables => {}
+        ProxyMode::Ipvs => command.push_str(
+            " && (command -v ipset >/dev/null || { echo \"ipset is not installed\"; exit 1; })",
+        ),
+        ProxyMode::Nftables => command.push_str(
+            " && (command -v nft >/dev/null || { echo \"nft is not installed\"; exit 1; })",
+        ),
+    }
+    command
+}
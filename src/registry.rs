@@ -0,0 +1,137 @@
+use crate::config::Config;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Registries needing any configuration, either mirrored or insecure.
+pub fn hosts(config: &Config) -> BTreeSet<String> {
+    config
+        .registry_mirrors
+        .keys()
+        .chain(config.insecure_registries.iter())
+        .cloned()
+        .collect()
+}
+
+pub fn is_insecure(config: &Config, host: &str) -> bool {
+    config
+        .insecure_registries
+        .iter()
+        .any(|insecure| insecure == host)
+}
+
+fn mirrors<'a>(config: &'a Config, host: &str) -> &'a [String] {
+    config
+        .registry_mirrors
+        .get(host)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+// Docker Hub is served from another host than the one images are named after.
+fn server(host: &str) -> String {
+    match host {
+        "docker.io" => "https://registry-1.docker.io".to_owned(),
+        _ => format!("https://{}", host),
+    }
+}
+
+/// Content of `/etc/containerd/certs.d/<host>/hosts.toml`, mirrors being tried
+/// in order before the registry itself.
+pub fn containerd_hosts_toml(config: &Config, host: &str) -> String {
+    let insecure = is_insecure(config, host);
+    let mut content = format!("server = \"{}\"\n", server(host));
+    for mirror in mirrors(config, host) {
+        writeln!(
+            &mut content,
+            "\n[host.\"{}\"]\n  capabilities = [\"pull\", \"resolve\"]",
+            mirror
+        )
+        .unwrap();
+    }
+    if insecure {
+        // Unverified HTTPS first, plain HTTP otherwise, like docker does.
+        for endpoint in [format!("https://{}", host), format!("http://{}", host)] {
+            writeln!(
+                &mut content,
+                "\n[host.\"{}\"]\n  capabilities = [\"pull\", \"resolve\", \"push\"]\n  skip_verify = true",
+                endpoint
+            )
+            .unwrap();
+        }
+    }
+    content
+}
+
+/// Registry section of containerd's CRI plugin, hosts being looked up under
+/// `certs.d` and credentials set inline for the sandbox image pulled by
+/// containerd itself.
+pub fn containerd_registry_toml(config: &Config) -> String {
+    let mut content = r#"    [plugins."io.containerd.grpc.v1.cri".registry]
+      config_path = "/etc/containerd/certs.d"
+"#
+    .to_owned();
+    let auths: BTreeMap<_, _> = config.registry_auths.iter().collect();
+    for (host, auth) in auths {
+        // JSON strings are valid TOML basic strings.
+        writeln!(
+            &mut content,
+            "      [plugins.\"io.containerd.grpc.v1.cri\".registry.configs.\"{}\".auth]\n        username = {}\n        password = {}",
+            host,
+            serde_json::Value::from(auth.username.as_str()),
+            serde_json::Value::from(auth.password.as_str())
+        )
+        .unwrap();
+    }
+    content
+}
+
+/// Content of a `/etc/containers/registries.conf.d/` drop-in, as read by CRI-O.
+pub fn containers_registries_conf(config: &Config) -> String {
+    // CRI-O refuses short image names unless told where to look them up.
+    let mut content = "unqualified-search-registries = [\"docker.io\"]\n".to_owned();
+    for host in hosts(config) {
+        let insecure = is_insecure(config, &host);
+        writeln!(
+            &mut content,
+            "\n[[registry]]\nprefix = \"{0}\"\nlocation = \"{0}\"\ninsecure = {1}",
+            host, insecure
+        )
+        .unwrap();
+        for mirror in mirrors(config, &host) {
+            // Locations are given without scheme here.
+            let location = mirror
+                .trim_start_matches("https://")
+                .trim_start_matches("http://");
+            writeln!(
+                &mut content,
+                "\n[[registry.mirror]]\nlocation = \"{}\"\ninsecure = {}",
+                location,
+                mirror.starts_with("http://")
+            )
+            .unwrap();
+        }
+    }
+    content
+}
+
+/// Docker style `config.json` holding registry credentials, understood by
+/// kubelet, docker and CRI-O alike.
+pub fn auth_json(config: &Config) -> Option<String> {
+    if config.registry_auths.is_empty() {
+        return None;
+    }
+    let auths: BTreeMap<&String, serde_json::Value> = config
+        .registry_auths
+        .iter()
+        .map(|(host, auth)| {
+            let token = STANDARD.encode(format!("{}:{}", auth.username, auth.password));
+            (host, serde_json::json!({ "auth": token }))
+        })
+        .collect();
+    Some(
+        serde_json::to_string_pretty(&serde_json::json!({ "auths": auths }))
+            .expect("Error happened when trying to serialize registry credentials"),
+    )
+}
@@ -1,12 +1,21 @@
+use crate::addon;
+use crate::arch;
+use crate::bundle;
+use crate::component_config;
 use crate::config;
 use crate::config::Config;
 use crate::deploy;
 use crate::init;
 use crate::install;
 use crate::join;
+use crate::kubeconfig;
+use crate::networking;
+use crate::runtime::Runtime;
+use crate::status;
+use crate::version::KubeVersion;
+use crate::workdir;
 use clap::{Parser, Subcommand};
 use std::env;
-use std::fs;
 use std::path::Path;
 
 #[derive(Parser)]
@@ -50,6 +59,9 @@ Example usage:
 "
 )]
 struct Cli {
+    /// Where rk8s keeps artifacts and clusters, `RK8S_HOME` or `/rk8s` by default.
+    #[arg(long, global = true)]
+    workdir: Option<String>,
     #[arg(long)]
     flag1: Option<String>,
     #[arg(long)]
@@ -64,45 +76,123 @@ enum Commands {
     Precheck,
     Init,
     Join,
-    Install { target: String },
-    Generate { target: String },
+    Install {
+        target: String,
+    },
+    Generate {
+        target: String,
+    },
+    Bundle {
+        action: String,
+        path: Option<String>,
+    },
+    Addon {
+        action: String,
+        name: Option<String>,
+    },
+    Kubeconfig {
+        #[command(subcommand)]
+        action: KubeconfigAction,
+    },
+    /// Report the health of every node of the configuration.
+    Status {
+        /// `table`, or `json` for scripts.
+        #[arg(long, default_value = "table")]
+        output: String,
+    },
+    /// Manage the clusters of the working directory.
+    Cluster {
+        #[command(subcommand)]
+        action: ClusterAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClusterAction {
+    /// List clusters, marking the current one.
+    List,
+    /// Make later commands act on the cluster `name`, creating it when missing.
+    Use { name: String },
+}
+
+#[derive(Subcommand)]
+enum KubeconfigAction {
+    /// Issue a kubeconfig with a client certificate signed by the cluster CA.
+    User {
+        #[arg(long)]
+        name: String,
+        /// Group (certificate organization) of the user, may be repeated.
+        #[arg(long)]
+        group: Vec<String>,
+        /// Validity of the certificate, such as `720h`.
+        #[arg(long, default_value = "720h")]
+        ttl: String,
+        /// ClusterRole the user is bound to.
+        #[arg(long)]
+        clusterrole: Option<String>,
+        /// Namespace the binding is limited to, cluster-wide otherwise.
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Where the kubeconfig is written, `pki/k8s/users/<name>.kubeconfig` of the cluster by default.
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Write the cluster admin kubeconfig on this machine.
+    Export {
+        #[arg(long, default_value = "admin.kubeconfig")]
+        output: String,
+        /// Merge into an existing kubeconfig instead, `~/.kube/config` when no path is given.
+        #[arg(long, num_args = 0..=1, default_missing_value = "~/.kube/config")]
+        merge: Option<String>,
+        /// Name of the cluster, user and context, the rk8s cluster name when merging.
+        #[arg(long)]
+        context_name: Option<String>,
+    },
 }
 
 pub fn run_command() {
-    // Change working directory
-    let work_root = Path::new("/rk8s");
-    if !work_root.is_dir() {
-        fs::create_dir("/rk8s").expect("Error happened when trying to create `/rk8s` directory");
-        fs::create_dir("/rk8s/cfg")
-            .expect("Error happened when trying to create `/rk8s/cfg` directory");
-        fs::create_dir("/rk8s/etcd")
-            .expect("Error happened when trying to create `/rk8s/etcd` directory");
-        fs::create_dir("/rk8s/docker")
-            .expect("Error happened when trying to create `/rk8s/docker` directory");
-        fs::create_dir("/rk8s/k8s")
-            .expect("Error happened when trying to create `/rk8s/k8s` directory");
-        fs::create_dir("/rk8s/preparation")
-            .expect("Error happened when trying to create `/rk8s/preparation` directory");
-    }
-    env::set_current_dir(work_root)
-        .expect("Error happened when trying to change working directory");
+    // Paths given on the command line are relative to where rk8s was invoked.
+    let origin_dir =
+        env::current_dir().expect("Error happened when trying to read working directory");
 
     let cli = Cli::parse();
+    workdir::init(cli.workdir.as_ref().map(|workdir| origin_dir.join(workdir)));
+    // Listing clusters or only reading their state does not change anything.
+    let _lock = match &cli.command {
+        Commands::Cluster { .. } | Commands::Status { .. } => None,
+        _ => Some(workdir::Lock::acquire()),
+    };
 
     match &cli.command {
         Commands::Deploy => {
             // Read configuration file.
-            let adm_config = Config::init();
+            let mut adm_config = Config::init();
+            arch::resolve(&mut adm_config);
+            // Refuse unsupported releases before touching any node.
+            tracing::info!("Deploying kubernetes {}", KubeVersion::from(&adm_config));
+            networking::validate(&adm_config);
+            component_config::validate(&adm_config);
             deploy::pre_check::start(&adm_config);
             deploy::etcd::start(&adm_config);
-            deploy::docker::start(&adm_config);
+            deploy::oci_runtime::start(&adm_config);
+            match Runtime::from(&adm_config) {
+                Runtime::Docker => deploy::docker::start(&adm_config),
+                Runtime::Containerd => deploy::containerd::start(&adm_config),
+                Runtime::CriO => deploy::crio::start(&adm_config),
+            }
+            deploy::oci_runtime::smoke_test(&adm_config);
             deploy::prepare_kube::start(&adm_config);
             deploy::kube_apiserver::start(&adm_config);
+            deploy::load_balancer::start(&adm_config);
             deploy::kube_controller_manager::start(&adm_config);
             deploy::kube_scheduler::start(&adm_config);
             deploy::kubectl::start(&adm_config);
+            deploy::node_local_load_balancer::start(&adm_config);
             deploy::kubelet::start(&adm_config);
             deploy::kube_proxy::start(&adm_config);
+            deploy::cni::start(&adm_config);
+            deploy::coredns::start(&adm_config);
+            deploy::addons::start(&adm_config);
         }
         Commands::Precheck => {
             // Read configuration file.
@@ -113,6 +203,9 @@ pub fn run_command() {
             // Read configuration file.
             let adm_config = Config::init();
             tracing::info!("Init subcommand invoked.");
+            tracing::info!("Initializing kubernetes {}", KubeVersion::from(&adm_config));
+            networking::validate(&adm_config);
+            component_config::validate(&adm_config);
             init::etcd::start(&adm_config);
             init::kube_apiserver::start(&adm_config);
             init::kube_controller_manager::start(&adm_config);
@@ -120,6 +213,8 @@ pub fn run_command() {
             init::kube_ctl::start(&adm_config);
             init::kube_let::start(&adm_config);
             init::kube_proxy::start(&adm_config);
+            init::cni::start(&adm_config);
+            init::coredns::start(&adm_config);
         }
         Commands::Join => {
             // Read configuration file.
@@ -137,7 +232,7 @@ pub fn run_command() {
                 }
                 "cfssl" => {
                     tracing::info!("Installing cfssl...");
-                    install::cfssl::start();
+                    install::cfssl::start(&adm_config);
                     tracing::info!("cfssl installation complete");
                 }
                 "docker" => {
@@ -145,6 +240,11 @@ pub fn run_command() {
                     install::docker::start(&adm_config);
                     tracing::info!("docker installation complete");
                 }
+                "containerd" => {
+                    tracing::info!("Installing containerd...");
+                    install::containerd::start(&adm_config);
+                    tracing::info!("containerd installation complete");
+                }
                 "kubernetes" => {
                     tracing::info!("Installing kubernetes...");
                     install::kubernetes::start(&adm_config);
@@ -159,14 +259,125 @@ pub fn run_command() {
             // Generate `config_template` do not require reading configuration.
             match target.as_str() {
                 "config" => {
-                    tracing::info!("Generating `config-template.yaml`...");
+                    tracing::info!("Generating `config.yaml`...");
                     config::generate_config_template();
-                    tracing::info!("`config_template.yaml` generated under `rk8s/cfg`");
+                    tracing::info!("`{}` generated", workdir::config_file().display());
                 }
                 _ => {
                     tracing::info!("Unknown target");
                 }
             }
         }
+        Commands::Bundle { action, path } => {
+            let path = path.as_ref().map(|path| origin_dir.join(path));
+            match action.as_str() {
+                "create" => {
+                    // Read configuration file.
+                    let mut adm_config = Config::init();
+                    arch::resolve(&mut adm_config);
+                    tracing::info!("Creating offline bundle...");
+                    bundle::create::start(&adm_config, path);
+                }
+                "import" => match path {
+                    Some(path) => {
+                        // Digests it knows are checked against the bundle.
+                        let adm_config = Config::init();
+                        tracing::info!("Importing offline bundle...");
+                        bundle::import::start(&adm_config, &path);
+                    }
+                    None => {
+                        tracing::info!("Please specify the bundle to import");
+                    }
+                },
+                _ => {
+                    tracing::info!("Unknown action");
+                }
+            }
+        }
+        Commands::Addon { action, name } => {
+            let mut adm_config = Config::init();
+            match (action.as_str(), name) {
+                ("list", _) => addon::list::start(&adm_config),
+                ("status", name) => addon::status::start(&adm_config, name.as_deref()),
+                ("enable", Some(name)) => addon::enable::start(&mut adm_config, name),
+                ("disable", Some(name)) => addon::disable::start(&mut adm_config, name),
+                ("enable" | "disable", None) => {
+                    tracing::info!("Please specify the add-on to {}", action);
+                }
+                _ => {
+                    tracing::info!("Unknown action");
+                }
+            }
+        }
+        Commands::Kubeconfig { action } => {
+            let adm_config = Config::init();
+            match action {
+                KubeconfigAction::User {
+                    name,
+                    group,
+                    ttl,
+                    clusterrole,
+                    namespace,
+                    output,
+                } => {
+                    let output = output.as_ref().map(|output| origin_dir.join(output));
+                    kubeconfig::user::start(
+                        &adm_config,
+                        name,
+                        group,
+                        ttl,
+                        clusterrole.as_deref(),
+                        namespace.as_deref(),
+                        output,
+                    );
+                }
+                KubeconfigAction::Export {
+                    output,
+                    merge,
+                    context_name,
+                } => {
+                    let home = env::var("HOME").unwrap_or_else(|_| "/root".to_owned());
+                    let merge = merge.as_ref().map(|merge| match merge.strip_prefix("~/") {
+                        Some(rest) => Path::new(&home).join(rest),
+                        None => origin_dir.join(merge),
+                    });
+                    kubeconfig::export::start(
+                        &adm_config,
+                        origin_dir.join(output),
+                        merge,
+                        context_name.as_deref(),
+                    );
+                }
+            }
+        }
+        Commands::Status { output } => {
+            let adm_config = Config::init();
+            status::start(&adm_config, output);
+        }
+        Commands::Cluster { action } => match action {
+            ClusterAction::List => {
+                for name in workdir::clusters() {
+                    let current = if name == workdir::cluster_name() {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    let config = workdir::home()
+                        .join("clusters")
+                        .join(&name)
+                        .join("config.yaml");
+                    let note = if config.is_file() {
+                        ""
+                    } else {
+                        " (not configured)"
+                    };
+                    println!("{} {}{}", current, name, note);
+                }
+            }
+            ClusterAction::Use { name } => {
+                workdir::use_cluster(name);
+                tracing::info!("Switched to cluster `{}`", name);
+            }
+        },
     }
 }
@@ -0,0 +1,64 @@
+use crate::config::Config;
+use std::fmt;
+
+/// Container runtime kubelet drives on every node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Runtime {
+    // Reached through dockershim, only available up to Kubernetes 1.23.
+    Docker,
+    Containerd,
+    CriO,
+}
+
+impl Runtime {
+    pub fn parse(name: &str) -> Option<Runtime> {
+        match name.trim() {
+            "docker" => Some(Runtime::Docker),
+            "containerd" => Some(Runtime::Containerd),
+            "crio" | "cri-o" => Some(Runtime::CriO),
+            _ => None,
+        }
+    }
+
+    /// Runtime set by `container_runtime` in `config.yaml`.
+    pub fn from(config: &Config) -> Runtime {
+        Runtime::parse(&config.container_runtime).unwrap_or_else(|| {
+            panic!(
+                "Container runtime `{}` is not supported, expected `docker`, `containerd` or `crio`",
+                config.container_runtime
+            )
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Runtime::Docker => "docker",
+            Runtime::Containerd => "containerd",
+            Runtime::CriO => "crio",
+        }
+    }
+
+    /// CRI socket kubelet connects to, none for dockershim.
+    pub fn endpoint(&self) -> Option<&'static str> {
+        match self {
+            Runtime::Docker => None,
+            Runtime::Containerd => Some("unix:///run/containerd/containerd.sock"),
+            Runtime::CriO => Some("unix:///var/run/crio/crio.sock"),
+        }
+    }
+
+    /// Systemd unit kubelet is started after.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Runtime::Docker => "docker.service",
+            Runtime::Containerd => "containerd.service",
+            Runtime::CriO => "crio.service",
+        }
+    }
+}
+
+impl fmt::Display for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,535 @@
+use crate::cni::Cni;
+use crate::config::Config;
+use crate::health::Component;
+use crate::networking;
+use crate::runtime::Runtime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::process::Command;
+
+// Certificates deployed by rk8s, private keys left aside.
+const CERT_DIRS: [&str; 2] = ["/opt/etcd/ssl", "/opt/kubernetes/ssl"];
+const ETCDCTL: &str = "ETCDCTL_API=3 /opt/etcd/bin/etcdctl --cacert=/opt/etcd/ssl/ca.pem \
+    --cert=/opt/etcd/ssl/server.pem --key=/opt/etcd/ssl/server-key.pem";
+
+#[derive(Serialize, Debug)]
+struct Report {
+    network_plugin: String,
+    etcd_leader: Option<String>,
+    /// Set when etcd could not be queried, members are unknown then.
+    etcd_error: Option<String>,
+    hosts: Vec<HostStatus>,
+}
+
+#[derive(Serialize, Debug)]
+struct HostStatus {
+    name: String,
+    ip: String,
+    role: String,
+    reachable: bool,
+    services: Vec<Service>,
+    /// Only known for masters.
+    apiserver_ready: Option<bool>,
+    node: Option<NodeStatus>,
+    etcd: Option<EtcdMember>,
+    cni: Option<CniPod>,
+    certificates: Vec<Certificate>,
+}
+
+#[derive(Serialize, Debug)]
+struct Service {
+    name: String,
+    state: String,
+    version: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct Certificate {
+    path: String,
+    days_left: i64,
+}
+
+#[derive(Serialize, Debug)]
+struct NodeStatus {
+    kubelet_version: String,
+    conditions: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug)]
+struct EtcdMember {
+    id: String,
+    leader: bool,
+    healthy: bool,
+    version: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct CniPod {
+    name: String,
+    phase: String,
+    ready: bool,
+    restarts: u64,
+}
+
+// The fields of `etcdctl -w json` and `kubectl -o json` read here.
+
+#[derive(Deserialize, Debug)]
+struct MemberList {
+    #[serde(default)]
+    members: Vec<Member>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Member {
+    #[serde(rename = "ID")]
+    id: u64,
+    #[serde(default)]
+    name: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct EndpointStatus {
+    Status: MemberStatus,
+}
+
+#[derive(Deserialize, Debug)]
+struct MemberStatus {
+    header: StatusHeader,
+    leader: u64,
+    version: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct StatusHeader {
+    member_id: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct List<T> {
+    items: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Node {
+    metadata: Metadata,
+    status: NodeState,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct NodeState {
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    nodeInfo: NodeInfo,
+}
+
+#[derive(Deserialize, Debug)]
+struct Condition {
+    #[serde(rename = "type")]
+    kind: String,
+    status: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct NodeInfo {
+    kubeletVersion: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct Metadata {
+    name: String,
+    #[serde(default)]
+    ownerReferences: Vec<OwnerReference>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OwnerReference {
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Pod {
+    metadata: Metadata,
+    spec: PodSpec,
+    status: PodState,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct PodSpec {
+    #[serde(default)]
+    nodeName: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct PodState {
+    #[serde(default)]
+    phase: String,
+    #[serde(default)]
+    containerStatuses: Vec<ContainerStatus>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct ContainerStatus {
+    ready: bool,
+    restartCount: u64,
+}
+
+/// Output of `command` on the node at `ip`, none when it cannot be reached.
+fn remote(ip: &str, command: &str) -> Option<String> {
+    let output = Command::new("ssh")
+        .arg("-o")
+        .arg("ConnectTimeout=5")
+        .arg(format!("root@{}", ip))
+        .arg(command)
+        .output()
+        .expect("Error happened when trying to query node status");
+    // ssh exits with 255 on connection failures.
+    match output.status.code() {
+        Some(255) => None,
+        _ => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+    }
+}
+
+/// Parse the JSON printed by `command` on the first master.
+fn query<T: for<'de> Deserialize<'de>>(config: &Config, command: &str) -> Option<T> {
+    let (ip, _) = config.first_master();
+    remote(ip, command).and_then(|output| serde_json::from_str(&output).ok())
+}
+
+/// etcdctl talking to the first master, etcd does not listen on localhost.
+fn etcdctl(config: &Config, args: &str) -> String {
+    let (ip, _) = config.first_master();
+    format!(
+        "{} --endpoints=https://{}:2379 {}",
+        ETCDCTL,
+        networking::host(ip),
+        args
+    )
+}
+
+/// Systemd units rk8s manages on a node, with the command printing the
+/// version of each.
+fn units(config: &Config, master: bool) -> Vec<(String, String)> {
+    let runtime = Runtime::from(config).unit().trim_end_matches(".service");
+    let mut units = vec![
+        ("etcd".to_owned(), "/opt/etcd/bin/etcd --version".to_owned()),
+        (runtime.to_owned(), format!("{} --version", runtime)),
+    ];
+    let kube: &[&str] = match master {
+        true => &[
+            "kube-apiserver",
+            "kube-controller-manager",
+            "kube-scheduler",
+            "kubelet",
+            "kube-proxy",
+        ],
+        false => &["kubelet", "kube-proxy"],
+    };
+    for unit in kube {
+        units.push((
+            unit.to_string(),
+            format!("/opt/kubernetes/bin/{} --version", unit),
+        ));
+    }
+    let balanced = match master {
+        true => config.load_balancer.is_some(),
+        false => config.node_local_load_balancer,
+    };
+    if balanced {
+        units.push(("haproxy".to_owned(), "haproxy -v".to_owned()));
+    }
+    if master && config.load_balancer.is_some() {
+        units.push(("keepalived".to_owned(), "keepalived --version".to_owned()));
+    }
+    units
+}
+
+/// Shell script printing one `service`, `apiserver` or `cert` line per fact
+/// about the node at `ip`.
+fn host_script(config: &Config, ip: &str, master: bool) -> String {
+    let mut script = String::new();
+    for (unit, version) in units(config, master) {
+        script.push_str(&format!(
+            "echo \"service {} $(systemctl is-active {}) \
+            $({} 2>&1 | grep -Eom1 'v?[0-9]+\\.[0-9]+\\.[0-9]+' | head -1)\"; ",
+            unit, unit, version
+        ));
+    }
+    if master {
+        script.push_str(&format!(
+            "if {}; then echo 'apiserver ready'; else echo 'apiserver not-ready'; fi; ",
+            Component::Apiserver.probe(config, ip)
+        ));
+    }
+    // Kubelet keeps the client certificates it rotated out next to the current one.
+    script.push_str(&format!(
+        "now=$(date +%s); for f in {}; do \
+        case $f in *-key.pem|*/kubelet-client-2*) continue;; esac; \
+        end=$(openssl x509 -enddate -noout -in $f 2>/dev/null | cut -d= -f2); \
+        [ -n \"$end\" ] && echo \"cert $f $(( ($(date -d \"$end\" +%s) - now) / 86400 ))\"; \
+        done; true",
+        CERT_DIRS
+            .iter()
+            .map(|dir| format!("{}/*.pem", dir))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    script
+}
+
+fn host_status(config: &Config, ip: &str, name: &str) -> HostStatus {
+    let master = name.contains("master");
+    let mut status = HostStatus {
+        name: name.to_owned(),
+        ip: ip.to_owned(),
+        role: match master {
+            true => "master".to_owned(),
+            false => "worker".to_owned(),
+        },
+        reachable: false,
+        services: Vec::new(),
+        apiserver_ready: None,
+        node: None,
+        etcd: None,
+        cni: None,
+        certificates: Vec::new(),
+    };
+    let Some(output) = remote(ip, &host_script(config, ip, master)) else {
+        return status;
+    };
+    status.reachable = true;
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["service", name, state, version @ ..] => status.services.push(Service {
+                name: name.to_string(),
+                state: state.to_string(),
+                version: version.first().map(|version| version.to_string()),
+            }),
+            ["apiserver", state] => status.apiserver_ready = Some(*state == "ready"),
+            ["cert", path, days] => {
+                if let Ok(days_left) = days.parse() {
+                    status.certificates.push(Certificate {
+                        path: path.to_string(),
+                        days_left,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    status
+}
+
+/// Gather the state of every node of the configuration, and of etcd,
+/// Kubernetes and the network plugin as seen from the first master.
+fn gather(config: &Config) -> Report {
+    let mut hosts: Vec<(&String, &String)> = config.instance_hosts.iter().collect();
+    hosts.sort_by_key(|(_, name)| name.as_str());
+    let mut report = Report {
+        network_plugin: Cni::from(config).to_string(),
+        etcd_leader: None,
+        etcd_error: None,
+        hosts: hosts
+            .into_iter()
+            .map(|(ip, name)| {
+                tracing::info!("Querying {}...", name);
+                host_status(config, ip, name)
+            })
+            .collect(),
+    };
+
+    tracing::info!("Querying etcd, nodes and network plugin pods...");
+    // Members etcd could not reach are missing from the statuses.
+    // A cluster always has members, none means etcd could not be queried.
+    let members = query::<MemberList>(config, &etcdctl(config, "member list -w json"))
+        .filter(|members| !members.members.is_empty());
+    if members.is_none() {
+        let (_, master) = config.first_master();
+        tracing::error!(
+            "Error happened when trying to list etcd members on {}",
+            master
+        );
+        report.etcd_error = Some(format!("members could not be listed on {}", master));
+    }
+    let statuses: Vec<EndpointStatus> = query(
+        config,
+        &etcdctl(config, "endpoint status --cluster -w json"),
+    )
+    .unwrap_or_default();
+    let leader = statuses.first().map(|status| status.Status.leader);
+    let nodes: HashMap<String, Node> = query::<List<Node>>(config, "kubectl get nodes -o json")
+        .map(|list| list.items)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|node| (node.metadata.name.clone(), node))
+        .collect();
+    let (namespace, daemonset) = Cni::from(config).daemonset();
+    let pods: Vec<Pod> = query::<List<Pod>>(
+        config,
+        &format!("kubectl -n {} get pods -o json", namespace),
+    )
+    .map(|list| list.items)
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|pod| {
+        pod.metadata
+            .ownerReferences
+            .iter()
+            .any(|owner| owner.name == daemonset)
+    })
+    .collect();
+
+    for host in &mut report.hosts {
+        // Members are named after their node when deployed.
+        let member = members.as_ref().and_then(|members| {
+            members
+                .members
+                .iter()
+                .find(|member| member.name == format!("etcd_{}", host.name))
+        });
+        if let Some(member) = member {
+            let status = statuses
+                .iter()
+                .find(|status| status.Status.header.member_id == member.id);
+            if leader == Some(member.id) {
+                report.etcd_leader = Some(host.name.clone());
+            }
+            host.etcd = Some(EtcdMember {
+                id: format!("{:x}", member.id),
+                leader: leader == Some(member.id),
+                healthy: status.is_some(),
+                version: status.map(|status| status.Status.version.clone()),
+            });
+        }
+        host.node = nodes.get(&host.name).map(|node| NodeStatus {
+            kubelet_version: node.status.nodeInfo.kubeletVersion.clone(),
+            conditions: node
+                .status
+                .conditions
+                .iter()
+                .map(|condition| (condition.kind.clone(), condition.status.clone()))
+                .collect(),
+        });
+        host.cni = pods
+            .iter()
+            .find(|pod| pod.spec.nodeName == host.name)
+            .map(|pod| CniPod {
+                name: pod.metadata.name.clone(),
+                phase: pod.status.phase.clone(),
+                ready: !pod.status.containerStatuses.is_empty()
+                    && pod.status.containerStatuses.iter().all(|c| c.ready),
+                restarts: pod
+                    .status
+                    .containerStatuses
+                    .iter()
+                    .map(|c| c.restartCount)
+                    .sum(),
+            });
+    }
+    report
+}
+
+/// `Ready` when the node is, followed by the pressure conditions it reports.
+fn node_summary(node: &Option<NodeStatus>) -> String {
+    let Some(node) = node else {
+        return "-".to_owned();
+    };
+    let mut summary = match node.conditions.get("Ready").map(String::as_str) {
+        Some("True") => vec!["Ready".to_owned()],
+        _ => vec!["NotReady".to_owned()],
+    };
+    summary.extend(
+        node.conditions
+            .iter()
+            .filter(|(kind, status)| kind.as_str() != "Ready" && status.as_str() == "True")
+            .map(|(kind, _)| kind.clone()),
+    );
+    summary.join(",")
+}
+
+fn print_table(report: &Report) {
+    println!(
+        "{:<20}{:<24}{:<8}{:<12}{:<12}{:<12}{:<16}CNI",
+        "HOST", "IP", "ROLE", "REACHABLE", "APISERVER", "ETCD", "NODE"
+    );
+    for host in &report.hosts {
+        let apiserver = match host.apiserver_ready {
+            Some(true) => "ready",
+            Some(false) => "not ready",
+            None => "-",
+        };
+        let etcd = match &host.etcd {
+            Some(member) if member.leader => "leader",
+            Some(member) if member.healthy => "follower",
+            Some(_) => "unhealthy",
+            None => "-",
+        };
+        let cni = match &host.cni {
+            Some(pod) if pod.ready => format!("{} ready", pod.phase),
+            Some(pod) => format!("{} not ready", pod.phase),
+            None => "-".to_owned(),
+        };
+        println!(
+            "{:<20}{:<24}{:<8}{:<12}{:<12}{:<12}{:<16}{}",
+            host.name,
+            host.ip,
+            host.role,
+            host.reachable,
+            apiserver,
+            etcd,
+            node_summary(&host.node),
+            cni
+        );
+    }
+    if let Some(error) = &report.etcd_error {
+        println!("etcd: {}", error);
+    }
+
+    println!();
+    println!("{:<20}{:<28}{:<12}VERSION", "HOST", "SERVICE", "STATE");
+    for host in &report.hosts {
+        for service in &host.services {
+            println!(
+                "{:<20}{:<28}{:<12}{}",
+                host.name,
+                service.name,
+                service.state,
+                service.version.as_deref().unwrap_or("-")
+            );
+        }
+    }
+
+    println!();
+    println!("{:<20}{:<48}DAYS LEFT", "HOST", "CERTIFICATE");
+    for host in &report.hosts {
+        for certificate in &host.certificates {
+            println!(
+                "{:<20}{:<48}{}",
+                host.name, certificate.path, certificate.days_left
+            );
+        }
+    }
+}
+
+/// Report the health of the cluster as a table, or as JSON for scripts.
+pub fn start(config: &Config, output: &str) {
+    if output != "table" && output != "json" {
+        panic!("Unknown output `{}`, expected `table` or `json`", output);
+    }
+    let report = gather(config);
+    match output {
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .expect("Error happened when trying to serialize status")
+        ),
+        _ => print_table(&report),
+    }
+}
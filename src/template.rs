@@ -0,0 +1,16 @@
+use regex::Regex;
+
+/// Render an embedded manifest, replacing every `{{ name }}` placeholder with
+/// its value.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let placeholder = Regex::new(r"\{\{\s*([A-Za-z0-9_]+)\s*\}\}").unwrap();
+    placeholder
+        .replace_all(template, |caps: &regex::Captures| {
+            let name = &caps[1];
+            match vars.iter().find(|(key, _)| *key == name) {
+                Some((_, value)) => value.to_string(),
+                None => panic!("No value given for `{{{{ {} }}}}` in template", name),
+            }
+        })
+        .into_owned()
+}
@@ -0,0 +1,154 @@
+use crate::config::Config;
+use crate::runtime::Runtime;
+use std::fmt;
+
+// Oldest and newest Kubernetes minor releases rk8s generates configuration for.
+pub const MIN_MINOR: u32 = 20;
+pub const MAX_MINOR: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KubeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KubeVersion {
+    /// Parse a release such as `v1.20.15`, download URLs need all three
+    /// numbers and pre-releases are not deployed.
+    pub fn parse(version: &str) -> Option<KubeVersion> {
+        let fields: Vec<u32> = version
+            .trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|field| match field.bytes().all(|b| b.is_ascii_digit()) {
+                true => field.parse().ok(),
+                false => None,
+            })
+            .collect::<Option<_>>()?;
+        let [major, minor, patch] = fields[..] else {
+            return None;
+        };
+        Some(KubeVersion {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    /// Target release of `config.yaml`, refusing the ones rk8s cannot deploy.
+    pub fn from(config: &Config) -> KubeVersion {
+        let version = KubeVersion::parse(&config.kubernetes_version).unwrap_or_else(|| {
+            panic!(
+                "`{}` is not a valid kubernetes version, expected something like `v1.20.15`",
+                config.kubernetes_version
+            )
+        });
+        if version.major != 1 || version.minor < MIN_MINOR || version.minor > MAX_MINOR {
+            panic!(
+                "Kubernetes {} is not supported, rk8s deploys v1.{} up to v1.{}",
+                version, MIN_MINOR, MAX_MINOR
+            );
+        }
+        if Runtime::from(config) == Runtime::Docker && !version.has_dockershim() {
+            panic!(
+                "Kubernetes {} removed dockershim, the docker runtime only works up to v1.23, \
+                please set `container_runtime` to `containerd`",
+                version
+            );
+        }
+        if let (Runtime::CriO, Some(crio)) = (Runtime::from(config), &config.crio_version) {
+            if KubeVersion::parse(crio).map(|crio| crio.minor) != Some(version.minor) {
+                panic!(
+                    "CRI-O {} does not match Kubernetes {}, their minor versions must be the same",
+                    crio, version
+                );
+            }
+        }
+        version
+    }
+
+    /// Kubelet talks to docker through the built-in dockershim, removed in 1.24
+    /// along with `--network-plugin`.
+    pub fn has_dockershim(&self) -> bool {
+        self.minor < 24
+    }
+
+    /// `--container-runtime` was removed in 1.27, the CRI endpoint moving into
+    /// KubeletConfiguration as `containerRuntimeEndpoint`.
+    pub fn has_container_runtime_flag(&self) -> bool {
+        self.minor < 27
+    }
+
+    /// Klog flags such as `--logtostderr` and `--log-dir` were removed in 1.26.
+    pub fn has_klog_flags(&self) -> bool {
+        self.minor < 26
+    }
+
+    /// Dual-stack networking is enabled by default from 1.21 on.
+    pub fn has_dual_stack(&self) -> bool {
+        self.minor >= 21
+    }
+
+    /// kube-proxy gained an `nftables` mode in 1.29.
+    pub fn has_nftables_proxy(&self) -> bool {
+        self.minor >= 29
+    }
+
+    /// The `nftables` mode is behind `NFTablesProxyMode` until 1.31.
+    pub fn has_nftables_proxy_gate(&self) -> bool {
+        self.minor < 31
+    }
+
+    /// Logging flags of every component.
+    pub fn log_flags(&self) -> Vec<(&'static str, &'static str)> {
+        if self.has_klog_flags() {
+            vec![
+                ("logtostderr", "false"),
+                ("v", "2"),
+                ("log-dir", "/opt/kubernetes/logs"),
+            ]
+        } else {
+            // Components log to journald through stderr.
+            vec![("v", "2")]
+        }
+    }
+}
+
+impl fmt::Display for KubeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        let cases = [
+            ("v1.29.3", Some((1, 29, 3))),
+            ("1.20.15", Some((1, 20, 15))),
+            (" v1.30.0 ", Some((1, 30, 0))),
+            ("v1.29", None),
+            ("1.29.0-rc.1", None),
+            ("1.x", None),
+            ("v1.29.0.1", None),
+            ("v1..0", None),
+            ("v1.+2.0", None),
+            ("", None),
+        ];
+        for (version, expected) in cases {
+            let parsed = KubeVersion::parse(version).map(|v| (v.major, v.minor, v.patch));
+            assert_eq!(parsed, expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let version = KubeVersion::parse("1.28.4").unwrap();
+        assert_eq!(version.to_string(), "v1.28.4");
+        assert_eq!(KubeVersion::parse(&version.to_string()), Some(version));
+    }
+}
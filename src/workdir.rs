@@ -0,0 +1,236 @@
+use std::env;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const DEFAULT_HOME: &str = "/rk8s";
+const DEFAULT_CLUSTER: &str = "default";
+// Name of the cluster commands act on, written by `rk8s cluster use`.
+const CURRENT_FILE: &str = "current-cluster";
+
+static WORKDIR: OnceLock<Workdir> = OnceLock::new();
+
+#[derive(Debug)]
+struct Workdir {
+    home: PathBuf,
+    cluster: String,
+}
+
+fn workdir() -> &'static Workdir {
+    WORKDIR
+        .get()
+        .expect("Working directory is used before being set up")
+}
+
+/// Set up the working directory, `workdir` or `RK8S_HOME` or `/rk8s`, and
+/// the one of the current cluster, then change into it.
+pub fn init(workdir: Option<PathBuf>) {
+    let root = workdir
+        .or_else(|| env::var_os("RK8S_HOME").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_HOME));
+    // Artifacts are shared by every cluster, as is what `rk8s install` downloads.
+    for dir in [
+        "preparation",
+        "etcd",
+        "docker",
+        "containerd",
+        "k8s",
+        "clusters",
+    ] {
+        let path = root.join(dir);
+        fs::create_dir_all(&path).unwrap_or_else(|_| {
+            panic!("Error happened when trying to create `{}`", path.display())
+        });
+    }
+    let root = root
+        .canonicalize()
+        .expect("Error happened when trying to resolve working directory");
+    let cluster = fs::read_to_string(root.join(CURRENT_FILE))
+        .map(|name| name.trim().to_owned())
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_CLUSTER.to_owned());
+    WORKDIR
+        .set(Workdir {
+            home: root,
+            cluster,
+        })
+        .expect("Working directory is set up twice");
+    prepare(cluster_name());
+    env::set_current_dir(home()).expect("Error happened when trying to change working directory");
+}
+
+/// Create the layout of the cluster `name`, phases generating certificates
+/// stage what they send to nodes under `staging` as well.
+fn prepare(name: &str) {
+    let root = home().join("clusters").join(name);
+    let dirs = [
+        "pki/etcd",
+        "pki/k8s",
+        "staging/etcd",
+        "staging/k8s",
+        "staging/docker",
+        "staging/containerd",
+        "staging/crio",
+        "staging/oci",
+        "staging/addons",
+        "staging/pre_check",
+        "state",
+    ];
+    for dir in dirs {
+        let path = root.join(dir);
+        fs::create_dir_all(&path).unwrap_or_else(|_| {
+            panic!("Error happened when trying to create `{}`", path.display())
+        });
+    }
+    for component in ["etcd", "k8s"] {
+        let link = root.join("pki").join(component).join("to_send");
+        if fs::symlink_metadata(&link).is_err() {
+            symlink(Path::new("../../staging").join(component), &link)
+                .expect("Error happened when trying to link staging directory");
+        }
+    }
+    if name == DEFAULT_CLUSTER {
+        migrate(&root);
+    }
+}
+
+/// Carry over a cluster set up before the per-cluster layout, which kept its
+/// configuration under `cfg` and its certificates in the phase directories.
+fn migrate(root: &Path) {
+    for component in ["etcd", "k8s"] {
+        let legacy = home().join(component);
+        let pki = root.join("pki").join(component);
+        if !legacy.join("ca.pem").is_file() || pki.join("ca.pem").exists() {
+            continue;
+        }
+        let entries = fs::read_dir(&legacy).unwrap_or_else(|_| {
+            panic!("Error happened when trying to read `{}`", legacy.display())
+        });
+        for entry in entries.flatten().filter(|entry| entry.path().is_file()) {
+            fs::copy(entry.path(), pki.join(entry.file_name()))
+                .expect("Error happened when trying to copy certificates");
+        }
+        tracing::info!(
+            "Certificates of `{}` copied to `{}`",
+            legacy.display(),
+            pki.display()
+        );
+    }
+    let legacy = home().join("cfg/config.yaml");
+    let config = root.join("config.yaml");
+    if legacy.is_file() && !config.exists() {
+        fs::copy(&legacy, &config).expect("Error happened when trying to copy `cfg/config.yaml`");
+        tracing::info!("`{}` copied to `{}`", legacy.display(), config.display());
+    }
+}
+
+pub fn home() -> &'static Path {
+    &workdir().home
+}
+
+pub fn cluster_name() -> &'static str {
+    &workdir().cluster
+}
+
+/// Directory of the current cluster.
+pub fn cluster() -> PathBuf {
+    home().join("clusters").join(cluster_name())
+}
+
+pub fn config_file() -> PathBuf {
+    cluster().join("config.yaml")
+}
+
+/// Certificates and kubeconfigs of `component`, `etcd` or `k8s`, also where
+/// their phases run.
+pub fn pki(component: &str) -> PathBuf {
+    cluster().join("pki").join(component)
+}
+
+/// Files sent to nodes, such as those of the container runtime.
+pub fn staging(component: &str) -> PathBuf {
+    cluster().join("staging").join(component)
+}
+
+pub fn preparation() -> PathBuf {
+    home().join("preparation")
+}
+
+/// Clusters having a directory, sorted by name.
+pub fn clusters() -> Vec<String> {
+    let mut clusters: Vec<String> = fs::read_dir(home().join("clusters"))
+        .expect("Error happened when trying to read `clusters`")
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    clusters.sort();
+    clusters
+}
+
+/// Make `name` the cluster later commands act on.
+pub fn use_cluster(name: &str) {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+    if !valid {
+        panic!(
+            "`{}` is not a valid cluster name, use letters, digits, `-`, `_` and `.`",
+            name
+        );
+    }
+    prepare(name);
+    fs::write(home().join(CURRENT_FILE), format!("{}\n", name))
+        .expect("Error happened when trying to write current cluster");
+}
+
+/// Held while a command works on the current cluster, another rk8s working
+/// on it at the same time would overwrite the files staged for nodes.
+///
+/// The kernel releases the lock when the process ends, however it ends.
+pub struct Lock {
+    _file: File,
+}
+
+impl Lock {
+    pub fn acquire() -> Lock {
+        let path = cluster().join("state/lock");
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .unwrap_or_else(|_| panic!("Error happened when trying to open `{}`", path.display()));
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                // The pid is written right after locking, it may be missing yet.
+                let holder = match pid.trim() {
+                    "" => String::new(),
+                    pid => format!(" ({})", pid),
+                };
+                panic!(
+                    "Cluster `{}` is in use by another rk8s process{}",
+                    cluster_name(),
+                    holder
+                );
+            }
+            Err(TryLockError::Error(_)) => {
+                panic!("Error happened when trying to lock `{}`", path.display())
+            }
+        }
+        // Only a hint for whoever finds the cluster in use.
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .expect("Error happened when trying to write lock file");
+        Lock { _file: file }
+    }
+}
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Generated by rk8s, fails once HAProxy on this master stops answering.
+curl -sfk --max-time 2 -o /dev/null https://{{ address }}:{{ port }}/healthz